@@ -0,0 +1,81 @@
+//! Measure how quickly exact mode maps the files listed by the compiler to
+//! monorepo-relative paths, using a stub compiler that prints a canned
+//! listing so that tsc itself is not measured.
+
+#[cfg(unix)]
+#[path = "../tests/support/mod.rs"]
+mod support;
+
+#[cfg(unix)]
+mod bench {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::{Path, PathBuf};
+
+    use criterion::{BenchmarkId, Criterion, Throughput};
+    use tsconfig_includes::options::EnumerationOptions;
+
+    use super::support::{generate_monorepo, PackageSpec};
+
+    /// Write a stub compiler printing `files` monorepo files, interleaved
+    /// with the library and dependency files tsc also lists.
+    fn write_stub_compiler(directory: &Path, monorepo_root: &Path, files: usize) -> PathBuf {
+        let mut listing = String::new();
+        for index in 0..files {
+            listing.push_str(&format!(
+                "{}/packages/a/src/module{}.ts\n",
+                monorepo_root.display(),
+                index
+            ));
+            if index % 10 == 0 {
+                listing.push_str(&format!(
+                    "{}/node_modules/dependency/index{}.d.ts\n",
+                    monorepo_root.display(),
+                    index
+                ));
+                listing.push_str("/opt/node_modules/typescript/lib/lib.d.ts\n");
+            }
+        }
+        let listing_file = directory.join("listing.txt");
+        fs::write(&listing_file, listing).unwrap();
+
+        let compiler = directory.join("tsc");
+        fs::write(
+            &compiler,
+            format!("#!/bin/sh\ncat '{}'\n", listing_file.display()),
+        )
+        .unwrap();
+        fs::set_permissions(&compiler, fs::Permissions::from_mode(0o755)).unwrap();
+        compiler
+    }
+
+    pub fn listed_files(c: &mut Criterion) {
+        let monorepo = generate_monorepo([PackageSpec::new("a", vec![])]);
+        let monorepo_root = fs::canonicalize(monorepo.path()).unwrap();
+
+        let mut group = c.benchmark_group("exact_listed_files");
+        for files in [10_000, 100_000] {
+            let bin = tempfile::tempdir().unwrap();
+            let compiler = write_stub_compiler(bin.path(), &monorepo_root, files);
+            let options = EnumerationOptions::default().compiler(compiler);
+
+            group.throughput(Throughput::Elements(files as u64));
+            group.bench_with_input(BenchmarkId::from_parameter(files), &files, |b, _| {
+                b.iter(|| {
+                    options
+                        .enumerate(&monorepo_root, ["packages/a/tsconfig.json"])
+                        .unwrap()
+                })
+            });
+        }
+        group.finish();
+    }
+}
+
+#[cfg(unix)]
+criterion::criterion_group!(benches, bench::listed_files);
+#[cfg(unix)]
+criterion::criterion_main!(benches);
+
+#[cfg(not(unix))]
+fn main() {}
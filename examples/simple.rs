@@ -1,13 +1,19 @@
+use std::error::Error;
 use std::io::{self, Write};
 use std::path::PathBuf;
+use std::process::ExitCode;
 
 use clap::{Parser, ValueEnum};
-use tsconfig_includes::{estimate, exact};
+use serde_json::json;
+use tsconfig_includes::estimate;
+use tsconfig_includes::options::{Calculation, EnumerationOptions};
+use tsconfig_includes::report::render_error_chain;
 
-#[derive(Clone, Debug, ValueEnum)]
+#[derive(Clone, Debug, PartialEq, ValueEnum)]
 enum EnumerationMethod {
     Estimate,
     Exact,
+    Verified,
 }
 
 #[derive(Debug, Parser)]
@@ -20,26 +26,110 @@ struct Cli {
     #[arg(long)]
     pub monorepo_root: PathBuf,
 
+    /// Emit one JSON line per package, followed by one JSON line per package
+    /// that failed to enumerate, instead of aborting on the first failure.
+    /// Requires the estimate enumeration method
+    #[arg(long)]
+    pub json_lines_errors: bool,
+
     /// List of tsconfig files to enumerate dependencies of
     #[arg()]
     pub tsconfig_files: Vec<PathBuf>,
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn main() -> ExitCode {
     let cli = Cli::parse();
-
-    match cli.enumeration_method {
-        EnumerationMethod::Estimate => {
-            let result =
-                estimate::tsconfig_includes_by_package_name(cli.monorepo_root, cli.tsconfig_files)?;
-            writeln!(io::stdout(), "{}", serde_json::to_string_pretty(&result)?)?;
-        }
-        EnumerationMethod::Exact => {
-            let result =
-                exact::tsconfig_includes_by_package_name(cli.monorepo_root, cli.tsconfig_files)?;
-            writeln!(io::stdout(), "{}", serde_json::to_string_pretty(&result)?)?;
+    if cli.json_lines_errors {
+        return match run_json_lines(cli) {
+            Ok(exit_code) => exit_code,
+            Err(err) => {
+                eprintln!("error: {}", render_error_chain(err.as_ref()));
+                ExitCode::FAILURE
+            }
+        };
+    }
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", render_error_chain(err.as_ref()));
+            ExitCode::FAILURE
         }
+    }
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    let calculation = match cli.enumeration_method {
+        EnumerationMethod::Estimate => Calculation::Estimate,
+        EnumerationMethod::Exact => Calculation::Exact,
+        EnumerationMethod::Verified => Calculation::Verified,
     };
+    let result = EnumerationOptions::default()
+        .calculation(calculation)
+        .enumerate(cli.monorepo_root, cli.tsconfig_files)?;
+    writeln!(io::stdout(), "{}", serde_json::to_string_pretty(&result)?)?;
 
     Ok(())
 }
+
+/// Write each package's files, then each failure, as a line of JSON, exiting
+/// unsuccessfully when anything failed.
+fn run_json_lines(cli: Cli) -> Result<ExitCode, Box<dyn Error>> {
+    if cli.enumeration_method != EnumerationMethod::Estimate {
+        return Err("--json-lines-errors requires --enumeration-method estimate".into());
+    }
+    let mut stdout = io::stdout().lock();
+    let (included_files, errors) = match EnumerationOptions::default()
+        .calculation(Calculation::Estimate)
+        .enumerate_partial(cli.monorepo_root, cli.tsconfig_files)
+    {
+        Ok(partial_result) => partial_result,
+        // Failures not attributable to any one package abort the run
+        Err(err) => {
+            let line = match err.source().and_then(|err| err.downcast_ref()) {
+                Some(err) => error_line(None, err),
+                None => json!({
+                    "package": null,
+                    "kind": "enumerate",
+                    "message": render_error_chain(&err),
+                }),
+            };
+            writeln!(stdout, "{}", line)?;
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+
+    let mut included_files: Vec<_> = included_files.into_iter().collect();
+    included_files.sort_unstable();
+    for (package, files) in included_files {
+        writeln!(stdout, "{}", json!({ "package": package, "files": files }))?;
+    }
+    for (package, err) in &errors {
+        writeln!(stdout, "{}", error_line(Some(package), err))?;
+    }
+
+    Ok(if errors.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn error_line(package: Option<&str>, err: &estimate::Error) -> serde_json::Value {
+    let kind = match err.kind() {
+        estimate::ErrorKind::MonorepoManifest { .. } => "monorepo_manifest",
+        estimate::ErrorKind::EnumeratePackageManifestsError { .. } => "enumerate_package_manifests",
+        estimate::ErrorKind::MissingInternalDependency { .. } => "missing_internal_dependency",
+        estimate::ErrorKind::DuplicatePackageName { .. } => "duplicate_package_name",
+        estimate::ErrorKind::PackageInMonorepoRoot { .. } => "package_in_monorepo_root",
+        estimate::ErrorKind::MissingPackageName { .. } => "missing_package_name",
+        estimate::ErrorKind::PackageNotInMonorepo { .. } => "package_not_in_monorepo",
+        estimate::ErrorKind::FromFile { .. } => "read_file",
+        estimate::ErrorKind::BuildWalker { .. } => "read_tsconfig",
+        estimate::ErrorKind::Walk { .. } => "walk",
+    };
+    json!({
+        "package": package,
+        "kind": kind,
+        "message": render_error_chain(err),
+    })
+}
@@ -0,0 +1,161 @@
+//! Incremental enumeration from a package's `.tsbuildinfo` file.
+//!
+//! When a composite (or incremental) project has been built, tsc leaves
+//! behind a `.tsbuildinfo` file recording the list of files that took part in
+//! the last compilation. Reading this list is far cheaper than spawning `tsc
+//! --listFilesOnly`, so exact mode can opt in to using it when the file is
+//! fresh.
+//!
+//! LIMITATION: the `.tsbuildinfo` format is an implementation detail of tsc
+//! and changes between compiler versions. This module understands the
+//! `program.fileNames` layout written by TypeScript 4.x and 5.x through 5.5
+//! as well as the top-level `fileNames` layout written by later versions. Any
+//! file we cannot parse is treated as stale, so callers fall back to invoking
+//! tsc.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::{
+    io::read_json_from_file,
+    path::{is_child_of_node_modules, is_monorepo_file, normalize, relative_path},
+    typescript_package::TypescriptConfigFile,
+};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompilerOptions {
+    #[serde(default)]
+    composite: bool,
+    #[serde(default)]
+    incremental: bool,
+    out_dir: Option<PathBuf>,
+    out_file: Option<PathBuf>,
+    root_dir: Option<PathBuf>,
+    ts_build_info_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TypescriptConfig {
+    #[serde(default)]
+    compiler_options: CompilerOptions,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BuildInfoProgram {
+    file_names: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BuildInfo {
+    // TypeScript <= 5.5
+    program: Option<BuildInfoProgram>,
+    // TypeScript >= 5.6
+    file_names: Option<Vec<String>>,
+}
+
+impl BuildInfo {
+    fn into_file_names(self) -> Option<Vec<String>> {
+        self.file_names
+            .or_else(|| self.program.map(|program| program.file_names))
+    }
+}
+
+/// Mirror tsc's `getTsBuildInfoEmitOutputFilePath` to locate the
+/// `.tsbuildinfo` file written for `tsconfig_path`.
+fn build_info_path(tsconfig_path: &Path, compiler_options: &CompilerOptions) -> Option<PathBuf> {
+    let config_directory = tsconfig_path.parent()?;
+    if let Some(ts_build_info_file) = &compiler_options.ts_build_info_file {
+        return Some(config_directory.join(ts_build_info_file));
+    }
+    if !compiler_options.composite && !compiler_options.incremental {
+        return None;
+    }
+    if let Some(out_file) = &compiler_options.out_file {
+        return Some(
+            config_directory
+                .join(out_file)
+                .with_extension("tsbuildinfo"),
+        );
+    }
+
+    let config_file_extensionless = tsconfig_path.with_extension("");
+    let build_info_extensionless = match &compiler_options.out_dir {
+        Some(out_dir) => {
+            let out_dir = config_directory.join(out_dir);
+            match &compiler_options.root_dir {
+                Some(root_dir) => out_dir.join(relative_path(
+                    &config_directory.join(root_dir),
+                    &config_file_extensionless,
+                )),
+                None => out_dir.join(config_file_extensionless.file_name()?),
+            }
+        }
+        None => config_file_extensionless,
+    };
+    Some(normalize(
+        &build_info_extensionless.with_extension("tsbuildinfo"),
+    ))
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Enumerate the files recorded in `tsconfig`'s `.tsbuildinfo`, as relative
+/// paths from the monorepo root.
+///
+/// Returns `None` when the build info is missing, unparseable, or stale,
+/// which is to say when the tsconfig, any recorded file in the monorepo, or
+/// any directory containing a recorded file has been modified since the
+/// build info was written. Checking directories catches files added next to
+/// (or removed from beside) previously-compiled sources, but files added to
+/// a directory that held no sources during the last build go unnoticed.
+pub(crate) fn tsconfig_includes_from_build_info(
+    monorepo_root: &Path,
+    tsconfig: &TypescriptConfigFile,
+) -> Option<Vec<PathBuf>> {
+    let tsconfig_path = monorepo_root.join(tsconfig.as_path());
+    let config: TypescriptConfig = read_json_from_file(&tsconfig_path)
+        .map_err(|err| debug!(path = ?tsconfig_path, error = %err, "unable to read tsconfig"))
+        .ok()?;
+    let build_info_path = build_info_path(&tsconfig_path, &config.compiler_options)?;
+    let build_info_modified = modified(&build_info_path)?;
+    if modified(&tsconfig_path)? > build_info_modified {
+        debug!(build_info = ?build_info_path, "build info is older than tsconfig");
+        return None;
+    }
+
+    let build_info: BuildInfo = read_json_from_file(&build_info_path)
+        .map_err(|err| debug!(path = ?build_info_path, error = %err, "unable to read build info"))
+        .ok()?;
+    let build_info_directory = build_info_path.parent()?;
+
+    let mut included_files = Vec::new();
+    for file_name in build_info.into_file_names()? {
+        let path = normalize(&build_info_directory.join(file_name));
+        if !is_monorepo_file(monorepo_root, &path) || is_child_of_node_modules(&path) {
+            continue;
+        }
+        let is_fresh =
+            |path: &Path| matches!(modified(path), Some(time) if time <= build_info_modified);
+        if !is_fresh(&path) || !path.parent().is_none_or(is_fresh) {
+            debug!(build_info = ?build_info_path, file = ?path, "build info is stale");
+            return None;
+        }
+        included_files.push(path.strip_prefix(monorepo_root).ok()?.to_owned());
+    }
+
+    Some(included_files)
+}
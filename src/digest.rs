@@ -0,0 +1,59 @@
+//! Stable digests of the files included by a package.
+
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest as _, Sha256};
+
+use crate::options::Digest;
+
+/// Compute the hex-encoded SHA-256 digest of a package's `files`.
+///
+/// Files are hashed in alphabetical order with `/` separators, so the digest
+/// is independent of compile order and platform. `resolve` maps each file to
+/// the path its contents are read from, which only happens for
+/// [`Digest::Contents`].
+pub(crate) fn package_digest<F>(
+    files: &[PathBuf],
+    digest: Digest,
+    resolve: F,
+) -> Result<String, (PathBuf, io::Error)>
+where
+    F: Fn(&Path) -> PathBuf,
+{
+    let mut files: Vec<&PathBuf> = files.iter().collect();
+    files.sort_unstable();
+
+    let mut hasher = Sha256::new();
+    for file in files {
+        hasher.update(portable_path(file).as_bytes());
+        hasher.update([0]);
+        if digest == Digest::Contents {
+            let path = resolve(file);
+            let mut contents = Vec::new();
+            File::open(&path)
+                .and_then(|mut handle| handle.read_to_end(&mut contents))
+                .map_err(|err| (path, err))?;
+            // Length-prefix the contents so file boundaries are unambiguous
+            hasher.update((contents.len() as u64).to_le_bytes());
+            hasher.update(&contents);
+        }
+    }
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// Render `path` with `/` separators regardless of platform.
+fn portable_path(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
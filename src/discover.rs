@@ -0,0 +1,228 @@
+//! Discover which packages in a monorepo are TypeScript packages, before
+//! enumerating anything.
+
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use typescript_tools::{configuration_file::ConfigurationFile, monorepo_manifest};
+
+use crate::{
+    extends::{resolve_extends, Extends},
+    io::read_json_from_file,
+    path::normalize,
+    typescript_package::{
+        package_manifests_by_package_name, PackageInMonorepoRootError, PackageManifestFile,
+        PackageManifestsError, TypescriptConfigFile,
+    },
+};
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct Error {
+    kind: ErrorKind,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ErrorKind::PackageInMonorepoRoot(path) => {
+                write!(f, "unexpected package in monorepo root: {:?}", path)
+            }
+            ErrorKind::DuplicatePackageName { name, paths } => write!(
+                f,
+                "packages {:?} and {:?} both declare the name {}",
+                paths[0], paths[1], name
+            ),
+            ErrorKind::Extends {
+                config_directory,
+                specifier,
+            } => write!(
+                f,
+                "unable to resolve tsconfig extends {:?} from {:?}",
+                specifier, config_directory
+            ),
+            _ => write!(f, "unable to discover tsconfig files"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ErrorKind::MonorepoManifest(err) => Some(err),
+            ErrorKind::EnumeratePackageManifestsError(err) => Some(err),
+            ErrorKind::DuplicatePackageName { name: _, paths: _ } => None,
+            ErrorKind::PackageInMonorepoRoot(_) => None,
+            ErrorKind::FromFile(err) => Some(err),
+            ErrorKind::Extends {
+                config_directory: _,
+                specifier: _,
+            } => None,
+        }
+    }
+}
+
+impl From<typescript_tools::io::FromFileError> for Error {
+    fn from(err: typescript_tools::io::FromFileError) -> Self {
+        Self {
+            kind: ErrorKind::MonorepoManifest(err),
+        }
+    }
+}
+
+impl From<typescript_tools::monorepo_manifest::EnumeratePackageManifestsError> for Error {
+    fn from(err: typescript_tools::monorepo_manifest::EnumeratePackageManifestsError) -> Self {
+        Self {
+            kind: ErrorKind::EnumeratePackageManifestsError(err),
+        }
+    }
+}
+
+impl From<PackageManifestsError> for Error {
+    fn from(err: PackageManifestsError) -> Self {
+        let kind = match err {
+            PackageManifestsError::Enumerate(err) => ErrorKind::EnumeratePackageManifestsError(err),
+            PackageManifestsError::DuplicatePackageName { name, paths } => {
+                ErrorKind::DuplicatePackageName { name, paths }
+            }
+        };
+        Self { kind }
+    }
+}
+
+impl From<crate::io::FromFileError> for Error {
+    fn from(err: crate::io::FromFileError) -> Self {
+        Self {
+            kind: ErrorKind::FromFile(err),
+        }
+    }
+}
+
+impl From<PackageInMonorepoRootError> for Error {
+    fn from(err: PackageInMonorepoRootError) -> Self {
+        Self {
+            kind: ErrorKind::PackageInMonorepoRoot(err.0),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ErrorKind {
+    #[non_exhaustive]
+    MonorepoManifest(typescript_tools::io::FromFileError),
+    #[non_exhaustive]
+    EnumeratePackageManifestsError(
+        typescript_tools::monorepo_manifest::EnumeratePackageManifestsError,
+    ),
+    #[non_exhaustive]
+    DuplicatePackageName { name: String, paths: [PathBuf; 2] },
+    #[non_exhaustive]
+    PackageInMonorepoRoot(PathBuf),
+    #[non_exhaustive]
+    FromFile(crate::io::FromFileError),
+    #[non_exhaustive]
+    Extends {
+        config_directory: PathBuf,
+        specifier: String,
+    },
+}
+
+/// List every package in the monorepo manifest that has a `tsconfig.json`
+/// beside its `package.json`. The return value pairs each scoped package name
+/// with the relative path from the monorepo root to its tsconfig file, sorted
+/// by package name.
+///
+/// The returned tsconfig files can be passed straight to any of the
+/// enumeration functions.
+///
+/// - `monorepo_root` may be an absolute path
+pub fn discover_tsconfigs<P>(monorepo_root: P) -> Result<Vec<(String, PathBuf)>, Error>
+where
+    P: AsRef<Path>,
+{
+    let monorepo_root = monorepo_root.as_ref();
+    let lerna_manifest = monorepo_manifest::MonorepoManifest::from_directory(monorepo_root)?;
+    let package_manifests_by_package_name = package_manifests_by_package_name(&lerna_manifest)?;
+
+    let mut tsconfig_files = Vec::new();
+    for (package_name, package_manifest) in package_manifests_by_package_name {
+        let tsconfig_file: TypescriptConfigFile =
+            PackageManifestFile::from(package_manifest.path()).try_into()?;
+        if monorepo_root.join(tsconfig_file.as_path()).is_file() {
+            tsconfig_files.push((package_name, tsconfig_file.as_path().to_owned()));
+        }
+    }
+    tsconfig_files.sort_unstable();
+    Ok(tsconfig_files)
+}
+
+/// The part of a tsconfig file that [`packages_extending`] reads.
+#[derive(Debug, Deserialize)]
+struct ExtendingTypescriptConfig {
+    extends: Option<Extends>,
+}
+
+/// List the packages whose tsconfig extends `base_tsconfig`, directly or
+/// through a chain of `extends`, as sorted scoped package names. Useful to
+/// find the packages affected by a change to a shared base configuration.
+///
+/// Only each package's `tsconfig.json`, as found by [`discover_tsconfigs`],
+/// is considered. A package whose tsconfig is `base_tsconfig` itself does
+/// not extend it.
+///
+/// - `monorepo_root` may be an absolute path
+/// - `base_tsconfig` should be a relative path from the monorepo root
+pub fn packages_extending<P, Q>(monorepo_root: P, base_tsconfig: Q) -> Result<Vec<String>, Error>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let monorepo_root = monorepo_root.as_ref();
+    let base_tsconfig = comparable_path(&monorepo_root.join(base_tsconfig));
+
+    let mut package_names = Vec::new();
+    for (package_name, tsconfig_file) in discover_tsconfigs(monorepo_root)? {
+        if extends_transitively(&monorepo_root.join(tsconfig_file), &base_tsconfig)? {
+            package_names.push(package_name);
+        }
+    }
+    Ok(package_names)
+}
+
+/// Whether the tsconfig at `tsconfig_path` extends `base_tsconfig`, given as
+/// a [`comparable_path`], through any chain of `extends`.
+fn extends_transitively(tsconfig_path: &Path, base_tsconfig: &Path) -> Result<bool, Error> {
+    let mut visited = HashSet::from([comparable_path(tsconfig_path)]);
+    let mut to_visit = vec![tsconfig_path.to_owned()];
+    while let Some(tsconfig_path) = to_visit.pop() {
+        let config: ExtendingTypescriptConfig = read_json_from_file(&tsconfig_path)?;
+        let config_directory = tsconfig_path.parent().unwrap_or_else(|| Path::new(""));
+        for specifier in config.extends.iter().flat_map(Extends::specifiers) {
+            let extended = resolve_extends(config_directory, specifier).ok_or_else(|| Error {
+                kind: ErrorKind::Extends {
+                    config_directory: config_directory.to_owned(),
+                    specifier: specifier.to_owned(),
+                },
+            })?;
+            let comparable = comparable_path(&extended);
+            if comparable == base_tsconfig {
+                return Ok(true);
+            }
+            // A cycle of extends is reported by enumeration, not here
+            if visited.insert(comparable) {
+                to_visit.push(extended);
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Express `path` so that two paths to the same file compare equal, even
+/// when one of them was resolved through a symlinked `node_modules`.
+fn comparable_path(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| normalize(path))
+}
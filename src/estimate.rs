@@ -1,82 +1,444 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     fmt::Display,
-    iter,
     path::{Path, PathBuf},
 };
 
-use globwalk::{FileType, GlobWalkerBuilder};
-use log::{debug, trace};
-use rayon::prelude::*;
+use ignore::{
+    gitignore::{Gitignore, GitignoreBuilder},
+    overrides::Override,
+};
+use rayon::{iter::Either, prelude::*};
 use serde::Deserialize;
-use typescript_tools::{configuration_file::ConfigurationFile, monorepo_manifest};
+use tracing::{debug, debug_span, trace};
+use typescript_tools::monorepo_manifest;
 
 use crate::{
+    extends::{resolve_extends, Extends},
+    imports::{follow_imports, ModuleResolution},
     io::read_json_from_file,
-    path::{self, *},
+    options::EnumerationOptions,
+    path::*,
+    reference_directive::follow_reference_directives,
     typescript_package::{
-        FromTypescriptConfigFileError, PackageInMonorepoRootError, PackageManifest,
-        PackageManifestFile, TypescriptConfigFile, TypescriptPackage,
+        count_by_package_name, group_by_package_name, group_by_tsconfig,
+        package_manifests_by_package_name, typescript_packages_to_enumerate, Enumeration,
+        FromTypescriptConfigFileError, PackageInMonorepoRootError, PackageManifestsError,
+        ResolvedPackages, TypescriptConfigFile, TypescriptPackage,
     },
+    walk::{include_matcher, IncludeWalker, Walker},
 };
 
-#[derive(Debug, Default, Deserialize)]
+/// Directories of package managers, which tsc excludes by default.
+const PACKAGE_MANAGER_DIRECTORIES: [&str; 3] =
+    ["node_modules", "bower_components", "jspm_packages"];
+
+/// The subset of a tsconfig's `compilerOptions` that affects which files are
+/// included, deserialized from the `compilerOptions` object.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct CompilerOptions {
-    #[serde(default)]
-    allow_js: bool,
-    #[serde(default)]
-    resolve_json_module: bool,
+#[non_exhaustive]
+pub struct CompilerOptions {
+    allow_js: Option<bool>,
+    allow_arbitrary_extensions: Option<bool>,
+    resolve_json_module: Option<bool>,
+    out_dir: Option<PathBuf>,
+    base_url: Option<PathBuf>,
+    paths: Option<BTreeMap<String, Vec<String>>>,
+    /// Directory of the tsconfig declaring `paths`, against which they
+    /// resolve in the absence of `baseUrl`.
+    #[serde(skip)]
+    paths_base: Option<PathBuf>,
 }
 
+impl CompilerOptions {
+    /// Resolve the options holding paths against `config_directory`, the
+    /// directory of the tsconfig declaring them.
+    fn declared_in(mut self, config_directory: &Path) -> CompilerOptions {
+        self.base_url = self
+            .base_url
+            .map(|base_url| normalize(&config_directory.join(base_url)));
+        self.out_dir = self
+            .out_dir
+            .map(|out_dir| normalize(&config_directory.join(out_dir)));
+        if self.paths.is_some() {
+            self.paths_base = Some(config_directory.to_owned());
+        }
+        self
+    }
+
+    /// Overlay the options set in `self` on top of those in `base`.
+    fn extend(self, base: CompilerOptions) -> CompilerOptions {
+        let (paths, paths_base) = match self.paths {
+            Some(paths) => (Some(paths), self.paths_base),
+            None => (base.paths, base.paths_base),
+        };
+        CompilerOptions {
+            allow_js: self.allow_js.or(base.allow_js),
+            allow_arbitrary_extensions: self
+                .allow_arbitrary_extensions
+                .or(base.allow_arbitrary_extensions),
+            resolve_json_module: self.resolve_json_module.or(base.resolve_json_module),
+            out_dir: self.out_dir.or(base.out_dir),
+            base_url: self.base_url.or(base.base_url),
+            paths,
+            paths_base,
+        }
+    }
+}
+
+/// The patterns tsc excludes from a tsconfig's include globs when the
+/// tsconfig does not declare `exclude`: the package manager directories
+/// `node_modules`, `bower_components`, and `jspm_packages`, followed by the
+/// `outDir` compiled output is written to, if any.
+///
+/// The package manager directories are excluded wherever they appear beneath
+/// the tsconfig's directory. The `outDir` is reported as it was given, so it
+/// is relative to the directory of the tsconfig declaring it.
+pub fn default_excludes(compiler_options: &CompilerOptions) -> Vec<String> {
+    PACKAGE_MANAGER_DIRECTORIES
+        .iter()
+        .map(|directory| directory.to_string())
+        .chain(
+            compiler_options
+                .out_dir
+                .as_ref()
+                .map(|out_dir| out_dir.to_string_lossy().into_owned()),
+        )
+        .collect()
+}
+
+/// A tsconfig file as written, before its `extends` chain is applied.
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct TypescriptConfig {
+struct RawTypescriptConfig {
+    extends: Option<Extends>,
     #[serde(default)]
     compiler_options: CompilerOptions,
-    // DISCUSS: how should we behave if `include` is not present?
+    include: Option<Vec<String>>,
+    files: Option<Vec<String>>,
+}
+
+#[derive(Debug)]
+struct TypescriptConfig {
+    compiler_options: CompilerOptions,
+    /// Include patterns followed by the `files` list, which the estimate
+    /// treats as literal patterns.
     include: Vec<String>,
+    /// Whether the tsconfig declares (or inherits) neither `include` nor
+    /// `files`, in which case tsc includes every file beneath its directory.
+    is_implicit_project: bool,
+}
+
+/// A tsconfig with its `extends` chain applied. Inherited `include` and
+/// `files` entries are relative to the directory of the tsconfig that
+/// declared them.
+#[derive(Debug, Default)]
+struct ExtendedTypescriptConfig {
+    compiler_options: CompilerOptions,
+    include: Option<(PathBuf, Vec<String>)>,
+    files: Option<(PathBuf, Vec<String>)>,
+}
+
+/// Apply the `extends` chain of `raw`, a tsconfig located in
+/// `config_directory`. `ancestors` holds the tsconfig files already being
+/// extended, to detect cycles.
+fn extend_typescript_config(
+    config_directory: &Path,
+    raw: RawTypescriptConfig,
+    ancestors: &mut Vec<PathBuf>,
+) -> Result<ExtendedTypescriptConfig, BuildWalkerErrorKind> {
+    let mut base = ExtendedTypescriptConfig::default();
+    for specifier in raw.extends.iter().flat_map(Extends::specifiers) {
+        let base_path = resolve_extends(config_directory, specifier).ok_or_else(|| {
+            BuildWalkerErrorKind::Extends {
+                config_directory: config_directory.to_owned(),
+                specifier: specifier.to_owned(),
+            }
+        })?;
+        if ancestors.contains(&base_path) {
+            return Err(BuildWalkerErrorKind::CircularExtends(base_path));
+        }
+        trace!(?base_path, "resolved extends");
+        let base_raw: RawTypescriptConfig = read_json_from_file(&base_path)?;
+        ancestors.push(base_path.clone());
+        let base_directory = base_path.parent().unwrap_or_else(|| Path::new(""));
+        let extended = extend_typescript_config(base_directory, base_raw, ancestors)?;
+        ancestors.pop();
+
+        // Later entries in an `extends` list override earlier ones
+        base = ExtendedTypescriptConfig {
+            compiler_options: extended.compiler_options.extend(base.compiler_options),
+            include: extended.include.or(base.include),
+            files: extended.files.or(base.files),
+        };
+    }
+
+    Ok(ExtendedTypescriptConfig {
+        compiler_options: raw
+            .compiler_options
+            .declared_in(config_directory)
+            .extend(base.compiler_options),
+        include: raw
+            .include
+            .map(|include| (config_directory.to_owned(), include))
+            .or(base.include),
+        files: raw
+            .files
+            .map(|files| (config_directory.to_owned(), files))
+            .or(base.files),
+    })
+}
+
+/// Express an `include` pattern declared by a tsconfig in `declared_in`
+/// relative to the leaf tsconfig's `config_directory` instead.
+///
+/// LIMITATION: patterns pointing outside of the leaf tsconfig's directory are
+/// kept, but never match since the estimate only walks that directory.
+fn rebase_include_pattern(config_directory: &Path, declared_in: &Path, pattern: String) -> String {
+    // ${configDir} always refers to the leaf tsconfig's directory
+    if pattern.contains("${configDir}") {
+        return pattern;
+    }
+    if config_directory == declared_in {
+        return pattern;
+    }
+    // Bases resolved through node_modules have canonical paths
+    let canonicalize =
+        |path: &Path| std::fs::canonicalize(path).unwrap_or_else(|_| normalize(path));
+    let prefix = relative_path(&canonicalize(config_directory), &canonicalize(declared_in));
+    if prefix.as_os_str().is_empty() {
+        return pattern;
+    }
+    format!(
+        "{}/{}",
+        prefix.to_string_lossy().replace('\\', "/"),
+        pattern.trim_start_matches("./")
+    )
 }
 
 impl TypescriptConfig {
-    /// LIMITATION: The TypeScript compiler docs state:
+    /// Read the tsconfig at `tsconfig_path`, applying its `extends` chain.
+    fn from_file(tsconfig_path: &Path) -> Result<Self, BuildWalkerError> {
+        let config_directory = tsconfig_path.parent().unwrap_or_else(|| Path::new(""));
+        Self::from_file_with_include_directory(tsconfig_path, config_directory)
+    }
+
+    /// Read the tsconfig at `tsconfig_path`, applying its `extends` chain,
+    /// with its own `include` and `files` entries relative to
+    /// `include_directory` rather than to the tsconfig's directory.
+    fn from_file_with_include_directory(
+        tsconfig_path: &Path,
+        include_directory: &Path,
+    ) -> Result<Self, BuildWalkerError> {
+        (|| {
+            let raw: RawTypescriptConfig = read_json_from_file(tsconfig_path)?;
+            let config_directory = tsconfig_path.parent().unwrap_or_else(|| Path::new(""));
+            Self::resolve(
+                config_directory,
+                include_directory,
+                raw,
+                vec![normalize(tsconfig_path)],
+            )
+        })()
+        .map_err(|kind| BuildWalkerError { kind })
+    }
+
+    /// Parse the contents of a tsconfig located in `config_directory`,
+    /// applying its `extends` chain.
+    fn from_str(config_directory: &Path, contents: &str) -> Result<Self, BuildWalkerError> {
+        (|| {
+            let raw: RawTypescriptConfig =
+                serde_json::from_str(contents).map_err(BuildWalkerErrorKind::Parse)?;
+            Self::resolve(config_directory, config_directory, raw, Vec::new())
+        })()
+        .map_err(|kind| BuildWalkerError { kind })
+    }
+
+    /// Apply the `extends` chain of `raw`, a tsconfig located in
+    /// `config_directory`, expressing every `include` and `files` entry
+    /// relative to `include_directory`. The tsconfig's own entries are
+    /// declared relative to `include_directory` too.
+    fn resolve(
+        config_directory: &Path,
+        include_directory: &Path,
+        raw: RawTypescriptConfig,
+        mut ancestors: Vec<PathBuf>,
+    ) -> Result<Self, BuildWalkerErrorKind> {
+        let extended = extend_typescript_config(config_directory, raw, &mut ancestors)?;
+        let is_implicit_project = extended.include.is_none() && extended.files.is_none();
+        // Without `include` or `files`, tsc includes everything beneath the tsconfig
+        let include = match is_implicit_project {
+            true => Some((config_directory.to_owned(), vec![String::from("**/*")])),
+            false => extended.include,
+        };
+        Ok(TypescriptConfig {
+            compiler_options: extended.compiler_options,
+            include: include
+                .into_iter()
+                .chain(extended.files)
+                .flat_map(|(declared_in, patterns)| {
+                    let declared_in = match declared_in == config_directory {
+                        true => include_directory.to_owned(),
+                        false => declared_in,
+                    };
+                    patterns.into_iter().map(move |pattern| {
+                        rebase_include_pattern(include_directory, &declared_in, pattern)
+                    })
+                })
+                .collect(),
+            is_implicit_project,
+        })
+    }
+
+    /// The `include` patterns, with directory-style patterns expanded to
+    /// match every file beneath the directory and brace alternatives expanded
+    /// into separate patterns.
+    fn include_patterns(&self) -> Vec<String> {
+        self.include
+            .iter()
+            // Normalize first, which substitutes the braces of ${configDir}
+            .map(|pattern| normalize_include_pattern(pattern))
+            .flat_map(|pattern| expand_braces(&pattern))
+            .collect()
+    }
+
+    /// How import specifiers in the package's files resolve to other files.
+    fn module_resolution(&self) -> ModuleResolution {
+        let mut extensions = vec![".ts", ".tsx", ".d.ts"];
+        if self.compiler_options.allow_js.unwrap_or(false) {
+            extensions.extend([".js", ".jsx"]);
+        }
+        let base_url = self.compiler_options.base_url.clone();
+        let paths = self.compiler_options.paths.clone().and_then(|paths| {
+            let paths_base = base_url
+                .clone()
+                .or_else(|| self.compiler_options.paths_base.clone())?;
+            Some((paths_base, paths))
+        });
+        ModuleResolution {
+            extensions,
+            allow_arbitrary_extensions: self
+                .compiler_options
+                .allow_arbitrary_extensions
+                .unwrap_or(false),
+            base_url,
+            paths,
+        }
+    }
+
+    /// Which files matched by the include globs have a file type the
+    /// TypeScript compiler would compile. From the TypeScript compiler docs:
     ///
     /// > If a glob pattern doesn’t include a file extension, then only files
     /// > with supported extensions are included (e.g. .ts, .tsx, and .d.ts by
     /// > default, with .js and .jsx if allowJs is set to true).
     ///
-    /// This implementation does not examine if globs contain extensions.
-    fn whitelisted_file_extensions(&self) -> HashSet<String> {
+    /// A glob that does name an extension, like `src/**/*.json`, additionally
+    /// admits files with that extension, but only through that glob. JSON
+    /// globs never admit config files like `tsconfig.json` or `package.json`,
+    /// which tsc does not treat as JSON modules, but a JSON file named
+    /// explicitly in `include` or `files` is always admitted.
+    fn included_file_types(&self, package_directory: &Path) -> IncludedFileTypes {
         let mut whitelist: Vec<String> = vec![
             String::from(".ts"),
             String::from(".tsx"),
             String::from(".d.ts"),
         ];
-        if self.compiler_options.allow_js {
+        if self.compiler_options.allow_js.unwrap_or(false) {
             whitelist.append(&mut vec![String::from(".js"), String::from(".jsx")]);
         }
+        if self
+            .compiler_options
+            .allow_arbitrary_extensions
+            .unwrap_or(false)
+        {
+            // Declaration shims for arbitrary extensions, like styles.d.css.ts
+            whitelist.push(String::from(".d.*.ts"));
+        }
+        let whitelisted_file_extensions: HashSet<String> = whitelist.into_iter().collect();
 
-        // add extensions from any glob that specifies one
-        let mut glob_extensions: Vec<String> = self
-            .include
-            .iter()
-            .filter(|pattern| is_glob(pattern))
-            .filter_map(|glob| glob_file_extension(glob))
-            .collect();
-
-        // FIXME: glob extensions apply to a specific glob, not every glob
-        whitelist.append(&mut glob_extensions);
-        whitelist
+        let resolve_json_module = self.compiler_options.resolve_json_module.unwrap_or(false);
+        let glob_file_extensions = self
+            .include_patterns()
             .into_iter()
-            .filter(|extension| {
-                if !extension.ends_with(".json") {
-                    return true;
+            .filter(|pattern| is_glob(pattern))
+            .filter_map(|glob| {
+                let extension = glob_file_extension(&glob)?;
+                if is_whitelisted_file_extension(
+                    &whitelisted_file_extensions,
+                    Path::new(&extension),
+                ) {
+                    return None;
                 }
                 // For JSON modules, the presence of a "src/**/*.json" include glob
                 // is not enough, JSON imports are still gated by this compiler option.
-                self.compiler_options.resolve_json_module
+                if extension.ends_with(".json") {
+                    if !resolve_json_module {
+                        return None;
+                    }
+                    let patterns = [glob]
+                        .into_iter()
+                        .chain(CONFIG_JSON_FILES.iter().map(|file| format!("!{}", file)));
+                    return Some((extension, include_matcher(package_directory, patterns)));
+                }
+                Some((extension, include_matcher(package_directory, [glob])))
+            })
+            .chain(
+                self.include_patterns()
+                    .into_iter()
+                    .filter(|pattern| {
+                        resolve_json_module && !is_glob(pattern) && pattern.ends_with(".json")
+                    })
+                    .map(|file| {
+                        // Anchor the named file to the package directory
+                        let file = format!("/{}", file.trim_start_matches('/'));
+                        (
+                            String::from(".json"),
+                            include_matcher(package_directory, [file]),
+                        )
+                    }),
+            )
+            .collect();
+
+        IncludedFileTypes {
+            package_directory: package_directory.to_owned(),
+            whitelisted_file_extensions,
+            glob_file_extensions,
+        }
+    }
+}
+
+/// Well-known config files that JSON include globs never admit.
+const CONFIG_JSON_FILES: [&str; 2] = ["tsconfig*.json", "package.json"];
+
+/// The file types admitted by a tsconfig's include globs.
+struct IncludedFileTypes {
+    package_directory: PathBuf,
+    /// Extensions admitted through any include glob.
+    whitelisted_file_extensions: HashSet<String>,
+    /// Extensions admitted only through the include glob naming them.
+    glob_file_extensions: Vec<(String, Override)>,
+}
+
+impl IncludedFileTypes {
+    /// Whether `path`, already known to match some include glob, has a file
+    /// type admitted by the glob it matches.
+    fn admits(&self, path: &Path) -> bool {
+        if is_whitelisted_file_extension(&self.whitelisted_file_extensions, path) {
+            return true;
+        }
+        let (Some(file), Ok(relative_path)) =
+            (path.to_str(), path.strip_prefix(&self.package_directory))
+        else {
+            return false;
+        };
+        self.glob_file_extensions
+            .iter()
+            .any(|(extension, matcher)| {
+                file.ends_with(extension.as_str())
+                    && matcher.matched(relative_path, false).is_whitelist()
             })
-            .collect()
     }
 }
 
@@ -93,6 +455,18 @@ impl Display for BuildWalkerError {
                 write!(f, "unexpected package in monorepo root: {:?}", path)
             }
             BuildWalkerErrorKind::IO(_) => write!(f, "unable to estimate tsconfig includes"),
+            BuildWalkerErrorKind::Parse(_) => write!(f, "unable to parse tsconfig"),
+            BuildWalkerErrorKind::Extends {
+                config_directory,
+                specifier,
+            } => write!(
+                f,
+                "unable to resolve tsconfig extends {:?} from {:?}",
+                specifier, config_directory
+            ),
+            BuildWalkerErrorKind::CircularExtends(path) => {
+                write!(f, "tsconfig {:?} extends itself", path)
+            }
         }
     }
 }
@@ -101,7 +475,13 @@ impl std::error::Error for BuildWalkerError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match &self.kind {
             BuildWalkerErrorKind::IO(err) => Some(err),
+            BuildWalkerErrorKind::Parse(err) => Some(err),
             BuildWalkerErrorKind::PackageInMonorepoRoot(_) => None,
+            BuildWalkerErrorKind::Extends {
+                config_directory: _,
+                specifier: _,
+            } => None,
+            BuildWalkerErrorKind::CircularExtends(_) => None,
         }
     }
 }
@@ -111,7 +491,16 @@ pub enum BuildWalkerErrorKind {
     #[non_exhaustive]
     IO(crate::io::FromFileError),
     #[non_exhaustive]
+    Parse(serde_json::Error),
+    #[non_exhaustive]
     PackageInMonorepoRoot(PathBuf),
+    #[non_exhaustive]
+    Extends {
+        config_directory: PathBuf,
+        specifier: String,
+    },
+    #[non_exhaustive]
+    CircularExtends(PathBuf),
 }
 
 impl From<crate::io::FromFileError> for BuildWalkerErrorKind {
@@ -129,10 +518,11 @@ pub struct WalkError {
 impl Display for WalkError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self.kind {
+            #[cfg(feature = "globwalk")]
             WalkErrorKind::WalkError(_) => write!(f, "unable to walk directory tree"),
+            WalkErrorKind::Ignore(_) => write!(f, "unable to walk directory tree"),
             // DISCUSS: is this something we can fully test for at compile time?
             // If so, we can use `expect` instead of exposing this possibility to the user.
-            WalkErrorKind::Path(_) => write!(f, "unable to strip path prefix"),
         }
     }
 }
@@ -140,12 +530,14 @@ impl Display for WalkError {
 impl std::error::Error for WalkError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match &self.kind {
-            WalkErrorKind::Path(err) => Some(err),
+            #[cfg(feature = "globwalk")]
             WalkErrorKind::WalkError(err) => Some(err),
+            WalkErrorKind::Ignore(err) => Some(err),
         }
     }
 }
 
+#[cfg(feature = "globwalk")]
 impl From<globwalk::WalkError> for WalkError {
     fn from(err: globwalk::WalkError) -> Self {
         Self {
@@ -154,86 +546,334 @@ impl From<globwalk::WalkError> for WalkError {
     }
 }
 
+impl From<ignore::Error> for WalkError {
+    fn from(err: ignore::Error) -> Self {
+        Self {
+            kind: WalkErrorKind::Ignore(err),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum WalkErrorKind {
-    #[non_exhaustive]
-    Path(path::StripPrefixError),
+    #[cfg(feature = "globwalk")]
     #[non_exhaustive]
     WalkError(globwalk::WalkError),
+    #[non_exhaustive]
+    Ignore(ignore::Error),
 }
 
 /// Use the `tsconfig_file`'s `include` configuration to enumerate the list of files
 /// matching include globs.
-fn tsconfig_includes_estimate<'a, 'b>(
-    monorepo_root: &'a Path,
-    tsconfig_file: &'b TypescriptConfigFile,
+///
+/// Globs resolve against the tsconfig's directory, unless `options` overrides
+/// the [include base](EnumerationOptions::include_base) of `tsconfig_file`.
+fn tsconfig_includes_estimate(
+    monorepo_root: &Path,
+    tsconfig_file: &TypescriptConfigFile,
+    options: &EnumerationOptions,
 ) -> Result<impl Iterator<Item = Result<PathBuf, WalkError>>, BuildWalkerError> {
     let monorepo_root = monorepo_root.to_owned();
-    let package_directory = tsconfig_file
-        .package_directory(&monorepo_root)
-        .map_err(|kind| BuildWalkerError {
-            kind: BuildWalkerErrorKind::PackageInMonorepoRoot(kind.0),
-        })?;
-    let tsconfig: TypescriptConfig =
-        read_json_from_file(monorepo_root.join(tsconfig_file.as_path())).map_err(|err| {
-            BuildWalkerError {
-                kind: BuildWalkerErrorKind::IO(err),
-            }
-        })?;
+    let tsconfig_path = monorepo_root.join(tsconfig_file.as_path());
+    let (package_directory, tsconfig) = match options.include_base_of(tsconfig_file) {
+        Some(include_base) => {
+            let include_directory = monorepo_root.join(include_base);
+            let tsconfig = TypescriptConfig::from_file_with_include_directory(
+                &tsconfig_path,
+                &include_directory,
+            )?;
+            (include_directory, tsconfig)
+        }
+        None => {
+            let package_directory =
+                tsconfig_file
+                    .package_directory(&monorepo_root)
+                    .map_err(|kind| BuildWalkerError {
+                        kind: BuildWalkerErrorKind::PackageInMonorepoRoot(kind.0),
+                    })?;
+            (
+                package_directory,
+                TypescriptConfig::from_file(&tsconfig_path)?,
+            )
+        }
+    };
 
-    let whitelisted_file_extensions = tsconfig.whitelisted_file_extensions();
+    Ok(walk_included_files(
+        monorepo_root,
+        package_directory,
+        tsconfig,
+        options.max_depth,
+    ))
+}
 
-    let is_whitelisted_file_extension = move |path: &Path| -> bool {
-        // Can't use path::extension here because some globs specify more than
-        // just a single extension (like .d.ts).
-        whitelisted_file_extensions.iter().any(|extension| {
-            path.to_str()
-                .expect("Path should contain only valid UTF-8")
-                .ends_with(extension)
-        })
+/// Whether `tsconfig_file` enumerates nothing because it declares neither
+/// `include` nor `files` and no files exist beneath its directory. tsc
+/// refuses to compile such a project, which usually signals a misconfigured
+/// package.
+pub(crate) fn is_empty_project(monorepo_root: &Path, tsconfig_file: &TypescriptConfigFile) -> bool {
+    let (Ok(package_directory), Ok(tsconfig)) = (
+        tsconfig_file.package_directory(monorepo_root),
+        TypescriptConfig::from_file(&monorepo_root.join(tsconfig_file.as_path())),
+    ) else {
+        return false;
     };
+    tsconfig.is_implicit_project
+        && walk_included_files(monorepo_root.to_owned(), package_directory, tsconfig, None)
+            .next()
+            .is_none()
+}
 
-    let monorepo_root_two = monorepo_root.clone();
-    let included_files = GlobWalkerBuilder::from_patterns(package_directory, &tsconfig.include)
-        .file_type(FileType::FILE)
-        .min_depth(0)
+/// Return the files matched by `tsconfig_file`'s include globs, as estimated
+/// with `options`, when every one of them is a declaration file, meaning
+/// there is nothing for tsc to compile.
+///
+/// Returns `None` when the package includes any other kind of file, includes
+/// no files at all, or cannot be estimated.
+pub(crate) fn declaration_only_includes(
+    monorepo_root: &Path,
+    tsconfig_file: &TypescriptConfigFile,
+    options: &EnumerationOptions,
+) -> Option<Vec<PathBuf>> {
+    let included_files: Vec<PathBuf> =
+        tsconfig_includes_estimate(monorepo_root, tsconfig_file, options)
+            .ok()?
+            .collect::<Result<_, _>>()
+            .ok()?;
+    let is_declaration_only = !included_files.is_empty()
+        && included_files
+            .iter()
+            .all(|file| file.to_str().is_some_and(|file| file.ends_with(".d.ts")));
+    is_declaration_only.then_some(included_files)
+}
+
+fn is_whitelisted_file_extension(
+    whitelisted_file_extensions: &HashSet<String>,
+    path: &Path,
+) -> bool {
+    let path = path.to_str().expect("Path should contain only valid UTF-8");
+    whitelisted_file_extensions
+        .iter()
+        .any(|extension| has_file_extension(path, extension))
+}
+
+/// Whether `path` ends with `extension`, which may span several dots, like
+/// `.d.ts`, or contain a wildcard, like `.d.*.ts`.
+pub(crate) fn has_file_extension(path: &str, extension: &str) -> bool {
+    // Can't use path::extension here because some extensions span more than
+    // one dot (like .d.ts).
+    match extension.split_once('*') {
+        // A pattern like .d.*.ts, where the wildcard stands for any one
+        // non-empty extension
+        Some((prefix, suffix)) => path.strip_suffix(suffix).is_some_and(|path| {
+            path.rsplit_once(prefix).is_some_and(|(_, wildcard)| {
+                !wildcard.is_empty() && !wildcard.contains(['.', '/', '\\'])
+            })
+        }),
+        None => path.ends_with(extension),
+    }
+}
+
+/// Build a matcher for the [`default_excludes`] of a tsconfig in
+/// `package_directory`.
+fn default_exclude_matcher(
+    package_directory: &Path,
+    compiler_options: &CompilerOptions,
+) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(package_directory);
+    for exclude in default_excludes(compiler_options) {
+        let exclude = match Path::new(&exclude).strip_prefix(package_directory) {
+            // Anchor the resolved outDir to the package directory
+            Ok(relative_path) => format!("/{}", relative_path.to_string_lossy()),
+            Err(_) => exclude,
+        };
+        builder
+            .add_line(None, &exclude)
+            .expect("should be able to create exclude matcher");
+    }
+    builder
         .build()
-        .expect("should be able to create glob walker")
-        .filter(move |maybe_dir_entry| match maybe_dir_entry {
-            Ok(dir_entry) => {
-                is_monorepo_file(&monorepo_root_two, dir_entry.path())
-                    && is_whitelisted_file_extension(dir_entry.path())
+        .expect("should be able to create exclude matcher")
+}
+
+/// Enumerate the files under `package_directory` matching `tsconfig`'s
+/// include globs and not excluded by its [`default_excludes`], as relative
+/// paths from the monorepo root.
+fn walk_included_files(
+    monorepo_root: PathBuf,
+    package_directory: PathBuf,
+    tsconfig: TypescriptConfig,
+    max_depth: Option<usize>,
+) -> impl Iterator<Item = Result<PathBuf, WalkError>> {
+    let include_patterns = tsconfig.include_patterns();
+    let included_file_types = tsconfig.included_file_types(&package_directory);
+    let default_excludes = default_exclude_matcher(&package_directory, &tsconfig.compiler_options);
+
+    let monorepo_root_two = monorepo_root.clone();
+    Walker::walk(package_directory, &include_patterns, max_depth)
+        .filter(move |maybe_path| match maybe_path {
+            Ok(path) => {
+                is_monorepo_file(&monorepo_root_two, path)
+                    && included_file_types.admits(path)
+                    && !default_excludes
+                        .matched_path_or_any_parents(path, false)
+                        .is_ignore()
             }
             Err(_) => true,
         })
-        .map(move |maybe_dir_entry| -> Result<PathBuf, WalkError> {
-            let dir_entry = maybe_dir_entry?;
-            let path = dir_entry
-                .path()
+        .map(move |maybe_path| -> Result<PathBuf, WalkError> {
+            let path = maybe_path?;
+            let path = path
                 .strip_prefix(&monorepo_root)
                 .map(ToOwned::to_owned)
-                .expect(&format!(
-                    "Should be able to strip monorepo-root prefix from path in monorepo: {:?}",
-                    dir_entry.path()
-                ));
+                .unwrap_or_else(|_| {
+                    panic!(
+                        "Should be able to strip monorepo-root prefix from path in monorepo: {:?}",
+                        path
+                    )
+                });
             Ok(path)
-        });
+        })
+}
 
+/// Use the `include` configuration of a tsconfig held in memory to enumerate
+/// the list of files matching include globs. The return value is a list of
+/// alphabetically-sorted relative paths from the monorepo root.
+///
+/// This lets editor integrations enumerate against unsaved edits to a
+/// tsconfig without writing it to disk. Unlike
+/// [`tsconfig_includes_by_package_name`], the package's internal
+/// dependencies are not enumerated.
+///
+/// There is no exact counterpart to this function because the TypeScript
+/// compiler only reads tsconfig files from disk.
+///
+/// - `monorepo_root` may be an absolute path
+/// - `package_directory` should be a relative path from the monorepo root,
+///   and is the directory against which include globs are resolved
+/// - `tsconfig` is the contents of the tsconfig file
+pub fn tsconfig_includes_from_str<P, Q>(
+    monorepo_root: P,
+    package_directory: Q,
+    tsconfig: &str,
+) -> Result<Vec<PathBuf>, Error>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let monorepo_root = monorepo_root.as_ref();
+    let package_directory = monorepo_root.join(package_directory);
+    let tsconfig = TypescriptConfig::from_str(&package_directory, tsconfig)?;
+    let mut included_files: Vec<_> =
+        walk_included_files(monorepo_root.to_owned(), package_directory, tsconfig, None)
+            .collect::<Result<_, _>>()?;
+    included_files.sort_unstable();
     Ok(included_files)
 }
 
+/// Determine whether the `include` configuration of `tsconfig_file` matches
+/// `file`, without enumerating every other file in the package.
+///
+/// This applies the same include globs and file-extension whitelist as
+/// [`tsconfig_includes_by_package_name`], but checks the single target path
+/// directly instead of walking the package directory.
+///
+/// - `monorepo_root` may be an absolute path
+/// - `tsconfig_file` and `file` should be relative paths from the monorepo root
+pub fn package_includes_file<P, Q, R>(
+    monorepo_root: P,
+    tsconfig_file: Q,
+    file: R,
+) -> Result<bool, Error>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+    R: AsRef<Path>,
+{
+    let monorepo_root = monorepo_root.as_ref();
+    let tsconfig_file: TypescriptConfigFile = tsconfig_file.as_ref().into();
+    let package_directory = tsconfig_file.package_directory(monorepo_root)?;
+    let tsconfig = TypescriptConfig::from_file(&monorepo_root.join(tsconfig_file.as_path()))?;
+
+    let file = monorepo_root.join(normalize(file.as_ref()));
+    if !file.is_file() {
+        return Ok(false);
+    }
+    let relative_path = match file.strip_prefix(&package_directory) {
+        Ok(relative_path) => relative_path,
+        Err(_) => return Ok(false),
+    };
+
+    let matcher = include_matcher(&package_directory, tsconfig.include_patterns());
+    let default_excludes = default_exclude_matcher(&package_directory, &tsconfig.compiler_options);
+    Ok(matcher.matched(relative_path, false).is_whitelist()
+        && tsconfig
+            .included_file_types(&package_directory)
+            .admits(&file)
+        && !default_excludes
+            .matched_path_or_any_parents(&file, false)
+            .is_ignore())
+}
+
+/// Like [`package_includes_file`], but estimate the files included by
+/// `tsconfig_file` as [`EnumerationOptions::enumerate`] would, walking the
+/// package instead of matching `file` alone.
+pub(crate) fn package_includes_file_with_options<P, Q, R>(
+    monorepo_root: P,
+    tsconfig_file: Q,
+    file: R,
+    options: &EnumerationOptions,
+) -> Result<bool, Error>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+    R: AsRef<Path>,
+{
+    let tsconfig_file: TypescriptConfigFile = normalize(tsconfig_file.as_ref()).into();
+    let file = normalize(file.as_ref());
+    Ok(tsconfig_included_files(monorepo_root.as_ref(), &tsconfig_file, options)?.contains(&file))
+}
+
 #[derive(Debug)]
 #[non_exhaustive]
 pub struct Error {
     kind: ErrorKind,
 }
 
+impl Error {
+    /// What went wrong, for callers that need to tell failures apart.
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+}
+
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self.kind {
             ErrorKind::PackageInMonorepoRoot(path) => {
                 write!(f, "unexpected package in monorepo root: {:?}", path)
             }
+            ErrorKind::DuplicatePackageName { name, paths } => write!(
+                f,
+                "packages {:?} and {:?} both declare the name {}",
+                paths[0], paths[1], name
+            ),
+            ErrorKind::MissingInternalDependency { dependent, missing } => write!(
+                f,
+                "package {} depends on {}, which is not a package in the monorepo",
+                dependent, missing
+            ),
+            ErrorKind::MissingPackageName(path) => {
+                write!(
+                    f,
+                    "package manifest {:?} does not declare a string name",
+                    path
+                )
+            }
+            ErrorKind::PackageNotInMonorepo(path) => write!(
+                f,
+                "tsconfig {:?} does not belong to a package in the monorepo",
+                path
+            ),
             _ => write!(f, "unable to estimate tsconfig includes"),
         }
     }
@@ -244,7 +884,14 @@ impl std::error::Error for Error {
         match &self.kind {
             ErrorKind::MonorepoManifest(err) => Some(err),
             ErrorKind::EnumeratePackageManifestsError(err) => Some(err),
+            ErrorKind::DuplicatePackageName { name: _, paths: _ } => None,
+            ErrorKind::MissingInternalDependency {
+                dependent: _,
+                missing: _,
+            } => None,
             ErrorKind::PackageInMonorepoRoot(_) => None,
+            ErrorKind::MissingPackageName(_) => None,
+            ErrorKind::PackageNotInMonorepo(_) => None,
             ErrorKind::FromFile(err) => Some(err),
             ErrorKind::BuildWalker(err) => Some(err),
             ErrorKind::Walk(err) => Some(err),
@@ -274,6 +921,18 @@ impl From<typescript_tools::monorepo_manifest::EnumeratePackageManifestsError> f
     }
 }
 
+impl From<PackageManifestsError> for Error {
+    fn from(err: PackageManifestsError) -> Self {
+        let kind = match err {
+            PackageManifestsError::Enumerate(err) => ErrorKind::EnumeratePackageManifestsError(err),
+            PackageManifestsError::DuplicatePackageName { name, paths } => {
+                ErrorKind::DuplicatePackageName { name, paths }
+            }
+        };
+        Self { kind }
+    }
+}
+
 impl From<crate::io::FromFileError> for Error {
     fn from(err: crate::io::FromFileError) -> Self {
         Self {
@@ -311,6 +970,15 @@ impl From<FromTypescriptConfigFileError> for Error {
                 ErrorKind::PackageInMonorepoRoot(path)
             }
             FromTypescriptConfigFileError::FromFile(err) => ErrorKind::FromFile(err),
+            FromTypescriptConfigFileError::MissingPackageName(path) => {
+                ErrorKind::MissingPackageName(path)
+            }
+            FromTypescriptConfigFileError::PackageNotInMonorepo(path) => {
+                ErrorKind::PackageNotInMonorepo(path)
+            }
+            FromTypescriptConfigFileError::RootPackageManifest(err) => {
+                ErrorKind::MonorepoManifest(err)
+            }
         };
         Self { kind }
     }
@@ -333,8 +1001,16 @@ pub enum ErrorKind {
         typescript_tools::monorepo_manifest::EnumeratePackageManifestsError,
     ),
     #[non_exhaustive]
+    DuplicatePackageName { name: String, paths: [PathBuf; 2] },
+    #[non_exhaustive]
+    MissingInternalDependency { dependent: String, missing: String },
+    #[non_exhaustive]
     PackageInMonorepoRoot(PathBuf),
     #[non_exhaustive]
+    MissingPackageName(PathBuf),
+    #[non_exhaustive]
+    PackageNotInMonorepo(PathBuf),
+    #[non_exhaustive]
     FromFile(crate::io::FromFileError),
     #[non_exhaustive]
     BuildWalker(BuildWalkerError),
@@ -357,81 +1033,343 @@ where
     T: IntoIterator,
     T::Item: AsRef<Path>,
 {
-    // REFACTOR: avoid duplicated code in estimate.rs and exact.rs
-    let lerna_manifest =
-        monorepo_manifest::MonorepoManifest::from_directory(monorepo_root.as_ref())?;
-    let package_manifests_by_package_name = lerna_manifest.package_manifests_by_package_name()?;
-    trace!("{:?}", lerna_manifest);
+    tsconfig_includes_by_package_name_with_options(
+        monorepo_root,
+        tsconfig_files,
+        &EnumerationOptions::default(),
+    )
+}
 
-    // As relative path from monorepo root
-    let transitive_internal_dependency_tsconfigs_inclusive_to_enumerate: HashSet<
-        TypescriptPackage,
-    > = tsconfig_files
-        .into_iter()
-        .map(|tsconfig_file| -> Result<Vec<TypescriptPackage>, Error> {
-            let tsconfig_file: TypescriptConfigFile =
-                monorepo_root.as_ref().join(tsconfig_file.as_ref()).into();
-            let package_manifest: PackageManifest = (&tsconfig_file).try_into()?;
-            let package_manifest = package_manifests_by_package_name
-                .get(&package_manifest.name)
-                .expect(&format!(
-                    "tsconfig {:?} should belong to a package in the lerna monorepo",
-                    tsconfig_file
-                ));
-
-            // RESUME: replace this comment with something sensible
-            // DISCUSS: what's the deal with transitive deps if enumerate is point and shoot?
-            let transitive_internal_dependencies_inclusive = {
-                // Enumerate internal dependencies (exclusive)
-                package_manifest
-                    .transitive_internal_dependency_package_names_exclusive(
-                        &package_manifests_by_package_name,
-                    )
-                    // Make this list inclusive of the target package
-                    .chain(iter::once(package_manifest))
-            };
-
-            Ok(transitive_internal_dependencies_inclusive
-                .map(
-                    |package_manifest| -> Result<_, PackageInMonorepoRootError> {
-                        let package_manifest_file =
-                            PackageManifestFile::from(package_manifest.path());
-                        let tsconfig_file: TypescriptConfigFile =
-                            package_manifest_file.try_into()?;
-                        let typescript_package = TypescriptPackage {
-                            scoped_package_name: package_manifest.contents.name.clone(),
-                            tsconfig_file,
-                        };
-                        Ok(typescript_package)
-                    },
-                )
-                .collect::<Result<_, _>>()?)
-        })
-        // REFACTOR: avoid intermediate allocations
-        .collect::<Result<Vec<_>, _>>()?
-        .into_iter()
-        .flatten()
-        .collect();
+/// Enumerate source code files used by the TypeScript compiler during
+/// compilation. The return value is a list of alphabetically-sorted relative
+/// paths from the monorepo root, grouped by the relative path from the
+/// monorepo root to the tsconfig file that includes them.
+///
+/// - `monorepo_root` may be an absolute path
+/// - `tsconfig_files` should be relative paths from the monorepo root
+pub fn tsconfig_includes_by_tsconfig<P, T>(
+    monorepo_root: P,
+    tsconfig_files: T,
+) -> Result<HashMap<PathBuf, Vec<PathBuf>>, Error>
+where
+    P: AsRef<Path> + Sync,
+    T: IntoIterator,
+    T::Item: AsRef<Path>,
+{
+    tsconfig_includes_by_tsconfig_with_options(
+        monorepo_root,
+        tsconfig_files,
+        &EnumerationOptions::default(),
+    )
+}
+
+/// Enumerate source code files used by the TypeScript compiler during
+/// compilation, like [`tsconfig_includes_by_package_name`], but without
+/// letting one broken package abort the whole run.
+///
+/// The return value pairs the alphabetically-sorted relative paths from the
+/// monorepo root of every package that was enumerated successfully, grouped
+/// by scoped package name, with the error encountered by every package that
+/// was not, sorted by scoped package name. Errors reading the monorepo
+/// manifest or resolving the packages to enumerate still abort the run.
+///
+/// - `monorepo_root` may be an absolute path
+/// - `tsconfig_files` should be relative paths from the monorepo root
+#[allow(clippy::type_complexity)]
+pub fn tsconfig_includes_by_package_name_partial<P, T>(
+    monorepo_root: P,
+    tsconfig_files: T,
+) -> Result<(HashMap<String, Vec<PathBuf>>, Vec<(String, Error)>), Error>
+where
+    P: AsRef<Path> + Sync,
+    T: IntoIterator,
+    T::Item: AsRef<Path>,
+{
+    tsconfig_includes_by_package_name_partial_with_options(
+        monorepo_root,
+        tsconfig_files,
+        &EnumerationOptions::default(),
+    )
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn tsconfig_includes_by_package_name_partial_with_options<P, T>(
+    monorepo_root: P,
+    tsconfig_files: T,
+    options: &EnumerationOptions,
+) -> Result<(HashMap<String, Vec<PathBuf>>, Vec<(String, Error)>), Error>
+where
+    P: AsRef<Path> + Sync,
+    T: IntoIterator,
+    T::Item: AsRef<Path>,
+{
+    let monorepo_root = monorepo_root.as_ref();
+    let typescript_packages =
+        resolve_typescript_packages(monorepo_root, tsconfig_files, options)?.typescript_packages;
+
+    let (included_files, mut errors): (Vec<_>, Vec<_>) = typescript_packages
+        .into_par_iter()
+        .partition_map(|typescript_package| {
+            match enumerate_package(monorepo_root, &typescript_package, options) {
+                Ok(included_files) => Either::Left((typescript_package, included_files)),
+                Err(err) => Either::Right((typescript_package.scoped_package_name, err)),
+            }
+        });
+    errors.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    Ok((group_by_package_name(included_files, false), errors))
+}
+
+/// Count the source code files matched by each package's tsconfig `include`
+/// globs, grouped by scoped package name.
+///
+/// Glob-walk matches are counted as they are found, so this never holds the
+/// full list of paths for a package in memory.
+///
+/// - `monorepo_root` may be an absolute path
+/// - `tsconfig_files` should be relative paths from the monorepo root
+pub fn tsconfig_include_counts_by_package_name<P, T>(
+    monorepo_root: P,
+    tsconfig_files: T,
+) -> Result<HashMap<String, usize>, Error>
+where
+    P: AsRef<Path> + Sync,
+    T: IntoIterator,
+    T::Item: AsRef<Path>,
+{
+    tsconfig_include_counts_by_package_name_with_options(
+        monorepo_root,
+        tsconfig_files,
+        &EnumerationOptions::default(),
+    )
+}
+
+pub(crate) fn tsconfig_include_counts_by_package_name_with_options<P, T>(
+    monorepo_root: P,
+    tsconfig_files: T,
+    options: &EnumerationOptions,
+) -> Result<HashMap<String, usize>, Error>
+where
+    P: AsRef<Path> + Sync,
+    T: IntoIterator,
+    T::Item: AsRef<Path>,
+{
+    let monorepo_root = monorepo_root.as_ref();
+    let typescript_packages =
+        resolve_typescript_packages(monorepo_root, tsconfig_files, options)?.typescript_packages;
+    count_by_package_name(typescript_packages, |typescript_package, count| {
+        for included_file in
+            tsconfig_includes_estimate(monorepo_root, &typescript_package.tsconfig_file, options)?
+        {
+            count(included_file?);
+        }
+        Ok(())
+    })
+}
+
+pub(crate) fn tsconfig_includes_by_package_name_with_options<P, T>(
+    monorepo_root: P,
+    tsconfig_files: T,
+    options: &EnumerationOptions,
+) -> Result<HashMap<String, Vec<PathBuf>>, Error>
+where
+    P: AsRef<Path> + Sync,
+    T: IntoIterator,
+    T::Item: AsRef<Path>,
+{
+    let included_files = enumerate(monorepo_root, tsconfig_files, options)?;
+    Ok(group_by_package_name(included_files, false))
+}
 
+pub(crate) fn tsconfig_includes_by_tsconfig_with_options<P, T>(
+    monorepo_root: P,
+    tsconfig_files: T,
+    options: &EnumerationOptions,
+) -> Result<HashMap<PathBuf, Vec<PathBuf>>, Error>
+where
+    P: AsRef<Path> + Sync,
+    T: IntoIterator,
+    T::Item: AsRef<Path>,
+{
+    let included_files = enumerate(monorepo_root, tsconfig_files, options)?;
+    Ok(group_by_tsconfig(included_files))
+}
+
+/// Resolve the requested tsconfig files, along with the tsconfig files of
+/// their internal dependencies up to `dependency_depth`, against the
+/// monorepo manifest.
+fn resolve_typescript_packages<Q>(
+    monorepo_root: &Path,
+    tsconfig_files: Q,
+    options: &EnumerationOptions,
+) -> Result<ResolvedPackages, Error>
+where
+    Q: IntoIterator,
+    Q::Item: AsRef<Path>,
+{
+    let lerna_manifest = monorepo_manifest::MonorepoManifest::from_directory(monorepo_root)?;
+    let package_manifests_by_package_name = package_manifests_by_package_name(&lerna_manifest)?;
+    trace!(?lerna_manifest, "read monorepo manifest");
+
+    let resolved_packages = typescript_packages_to_enumerate(
+        monorepo_root,
+        tsconfig_files,
+        &package_manifests_by_package_name,
+        options,
+    )?;
+    if !options.allow_missing_internal_dependencies {
+        if let Some((dependent, missing)) = resolved_packages.missing_internal_dependencies.first()
+        {
+            return Err(Error {
+                kind: ErrorKind::MissingInternalDependency {
+                    dependent: dependent.clone(),
+                    missing: missing.clone(),
+                },
+            });
+        }
+    }
     debug!(
-        "transitive_internal_dependency_tsconfigs_inclusive_to_enumerate: {:?}",
-        transitive_internal_dependency_tsconfigs_inclusive_to_enumerate
+        packages = resolved_packages.typescript_packages.len(),
+        without_tsconfig = resolved_packages.packages_without_tsconfig.len(),
+        "resolved packages to enumerate"
     );
+    Ok(resolved_packages)
+}
 
-    let included_files: HashMap<String, Vec<PathBuf>> =
-        transitive_internal_dependency_tsconfigs_inclusive_to_enumerate
-            .into_par_iter()
-            .map(|typescript_package| -> Result<(_, _), Error> {
-                // This relies on the assumption that tsconfig.json is always the name of the tsconfig file
-                let tsconfig_file = &typescript_package.tsconfig_file;
-                let mut included_files: Vec<_> =
-                    tsconfig_includes_estimate(monorepo_root.as_ref(), tsconfig_file)?
-                        .collect::<Result<_, _>>()?;
-                included_files.sort_unstable();
-                Ok((typescript_package.scoped_package_name, included_files))
-            })
-            .collect::<Result<HashMap<_, _>, _>>()?;
+pub(crate) fn enumerate<P, T>(
+    monorepo_root: P,
+    tsconfig_files: T,
+    options: &EnumerationOptions,
+) -> Result<Vec<(TypescriptPackage, Vec<PathBuf>)>, Error>
+where
+    P: AsRef<Path> + Sync,
+    T: IntoIterator,
+    T::Item: AsRef<Path>,
+{
+    Ok(enumerate_detailed(monorepo_root, tsconfig_files, options)?.included_files)
+}
 
-    debug!("tsconfig_includes: {:?}", included_files);
+/// Declaration files named directly by `compilerOptions.paths`
+/// substitutions, like `"globals": ["./types/globals.d.ts"]`, as relative
+/// paths from the monorepo root. Wildcard substitutions, files that do not
+/// exist, and files outside the monorepo or inside `node_modules` are
+/// skipped.
+fn path_mapped_declarations(
+    monorepo_root: &Path,
+    module_resolution: &ModuleResolution,
+) -> Vec<PathBuf> {
+    let Some((paths_base, paths)) = &module_resolution.paths else {
+        return Vec::new();
+    };
+    let monorepo_root = normalize(monorepo_root);
+    paths
+        .values()
+        .flatten()
+        .filter(|substitution| !substitution.contains('*') && substitution.ends_with(".d.ts"))
+        .map(|substitution| normalize(&paths_base.join(substitution)))
+        .filter(|declaration| !is_child_of_node_modules(declaration) && declaration.is_file())
+        .filter_map(|declaration| {
+            declaration
+                .strip_prefix(&monorepo_root)
+                .ok()
+                .map(ToOwned::to_owned)
+        })
+        .collect()
+}
+
+/// Enumerate the files included by a single package, sorted and rebased
+/// according to `options`.
+fn enumerate_package(
+    monorepo_root: &Path,
+    typescript_package: &TypescriptPackage,
+    options: &EnumerationOptions,
+) -> Result<Vec<PathBuf>, Error> {
+    let _span = debug_span!(
+        "enumerate_package",
+        package = %typescript_package.scoped_package_name,
+        tsconfig = ?typescript_package.tsconfig_file.as_path(),
+        calculation = "estimate",
+    )
+    .entered();
+    let included_files =
+        tsconfig_included_files(monorepo_root, &typescript_package.tsconfig_file, options)?;
+    let mut included_files = options.emitted_files(typescript_package, included_files);
+    included_files.sort_unstable();
+    debug!(files = included_files.len(), "enumerated package");
     Ok(included_files)
 }
+
+/// Enumerate the files included by `tsconfig_file` according to `options`,
+/// as relative paths from the monorepo root.
+fn tsconfig_included_files(
+    monorepo_root: &Path,
+    tsconfig_file: &TypescriptConfigFile,
+    options: &EnumerationOptions,
+) -> Result<Vec<PathBuf>, Error> {
+    let mut included_files: Vec<_> =
+        tsconfig_includes_estimate(monorepo_root, tsconfig_file, options)?
+            .collect::<Result<_, _>>()?;
+    if options.include_path_mapped_declarations {
+        let tsconfig = TypescriptConfig::from_file(&monorepo_root.join(tsconfig_file.as_path()))?;
+        for declaration in path_mapped_declarations(monorepo_root, &tsconfig.module_resolution()) {
+            if !included_files.contains(&declaration) {
+                included_files.push(declaration);
+            }
+        }
+    }
+    if options.follow_imports {
+        let tsconfig = TypescriptConfig::from_file(&monorepo_root.join(tsconfig_file.as_path()))?;
+        included_files = follow_imports(
+            monorepo_root,
+            included_files,
+            &tsconfig.module_resolution(),
+            options.resolve_path_aliases,
+        );
+    }
+    if options.follow_reference_directives {
+        included_files = follow_reference_directives(monorepo_root, included_files);
+    }
+    Ok(included_files)
+}
+
+/// Like [`enumerate`], but also return the internal dependencies skipped for
+/// lacking a tsconfig.
+pub(crate) fn enumerate_detailed<P, T>(
+    monorepo_root: P,
+    tsconfig_files: T,
+    options: &EnumerationOptions,
+) -> Result<Enumeration, Error>
+where
+    P: AsRef<Path> + Sync,
+    T: IntoIterator,
+    T::Item: AsRef<Path>,
+{
+    // As relative path from monorepo root
+    let resolved_packages =
+        resolve_typescript_packages(monorepo_root.as_ref(), tsconfig_files, options)?;
+    let warnings = resolved_packages
+        .missing_internal_dependency_warnings()
+        .collect();
+    let ResolvedPackages {
+        typescript_packages,
+        packages_without_tsconfig,
+        missing_internal_dependencies: _,
+        dependency_graph,
+    } = resolved_packages;
+
+    let included_files: Vec<(TypescriptPackage, Vec<PathBuf>)> = typescript_packages
+        .into_par_iter()
+        .map(|typescript_package| {
+            let included_files =
+                enumerate_package(monorepo_root.as_ref(), &typescript_package, options)?;
+            Ok((typescript_package, included_files))
+        })
+        .collect::<Result<_, Error>>()?;
+
+    Ok(Enumeration {
+        included_files,
+        warnings,
+        packages_without_tsconfig,
+        dependency_graph,
+    })
+}
@@ -1,24 +1,34 @@
 use std::{
+    borrow::Cow,
+    cmp::Reverse,
     collections::{HashMap, HashSet},
     fmt::Display,
-    iter,
+    io::{BufRead, BufReader, Read},
+    mem,
+    ops::ControlFlow,
     path::{Path, PathBuf},
-    process::Command,
+    process::{Child, Command, ExitStatus, Stdio},
     string,
+    thread::{self, JoinHandle},
 };
 
-use log::{debug, trace};
 use rayon::prelude::*;
+use serde::Deserialize;
+use tracing::{debug, debug_span, trace, warn};
 use typescript_tools::{configuration_file::ConfigurationFile, monorepo_manifest};
 
 use crate::{
-    path::{
-        self, is_child_of_node_modules, is_monorepo_file,
-        remove_relative_path_prefix_from_absolute_path,
-    },
+    build_info::tsconfig_includes_from_build_info,
+    estimate::{declaration_only_includes, is_empty_project},
+    io::read_json_from_file,
+    options::EnumerationOptions,
+    path::{self, monorepo_relative_path},
+    result::Warning,
     typescript_package::{
-        FromTypescriptConfigFileError, PackageInMonorepoRootError, PackageManifest,
-        PackageManifestFile, TypescriptConfigFile, TypescriptPackage,
+        count_by_package_name, group_by_package_name, group_by_tsconfig,
+        package_manifests_by_package_name, typescript_packages_to_enumerate, Enumeration,
+        FromTypescriptConfigFileError, PackageInMonorepoRootError, PackageManifestFile,
+        PackageManifestsError, ResolvedPackages, TypescriptConfigFile, TypescriptPackage,
     },
 };
 
@@ -28,10 +38,23 @@ pub struct EnumerateError {
     kind: EnumerateErrorKind,
 }
 
+impl EnumerateError {
+    /// What went wrong, for callers that need to tell failures apart.
+    pub fn kind(&self) -> &EnumerateErrorKind {
+        &self.kind
+    }
+}
+
 impl Display for EnumerateError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self.kind {
             EnumerateErrorKind::Command(_) => write!(f, "unable to spawn child process"),
+            EnumerateErrorKind::CompilerNotFound { program } => write!(
+                f,
+                "unable to find TypeScript compiler {:?}, install it with `npm i -g typescript` \
+                 or configure another with `EnumerationOptions::compiler`",
+                program
+            ),
             EnumerateErrorKind::TypescriptCompiler { command, error } => {
                 writeln!(
                     f,
@@ -43,13 +66,13 @@ impl Display for EnumerateError {
             EnumerateErrorKind::InvalidUtf8(_) => {
                 write!(f, "command output included invalid UTF-8")
             }
-            EnumerateErrorKind::StripPrefix(_) => write!(f, "unable to manipulate file path"),
             EnumerateErrorKind::PackageInMonorepoRoot(path) => {
                 write!(f, "unexpected package in monorepo root: {:?}", path)
             }
             EnumerateErrorKind::Canonicalize { path, inner: _ } => {
                 write!(f, "unable to canonicalize path {:?}", path)
             }
+            EnumerateErrorKind::Stdout(_) => write!(f, "unable to read command output"),
         }
     }
 }
@@ -58,14 +81,15 @@ impl std::error::Error for EnumerateError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match &self.kind {
             EnumerateErrorKind::Command(err) => Some(err),
+            EnumerateErrorKind::CompilerNotFound { program: _ } => None,
             EnumerateErrorKind::TypescriptCompiler {
                 command: _,
                 error: _,
             } => None,
             EnumerateErrorKind::InvalidUtf8(err) => Some(err),
-            EnumerateErrorKind::StripPrefix(err) => Some(err),
             EnumerateErrorKind::PackageInMonorepoRoot(_) => None,
             EnumerateErrorKind::Canonicalize { path: _, inner } => Some(inner),
+            EnumerateErrorKind::Stdout(err) => Some(err),
         }
     }
 }
@@ -75,18 +99,33 @@ pub enum EnumerateErrorKind {
     #[non_exhaustive]
     Command(std::io::Error),
     #[non_exhaustive]
+    CompilerNotFound { program: String },
+    #[non_exhaustive]
     TypescriptCompiler { command: String, error: Vec<u8> },
     #[non_exhaustive]
     InvalidUtf8(string::FromUtf8Error),
     #[non_exhaustive]
-    StripPrefix(path::StripPrefixError),
-    #[non_exhaustive]
     PackageInMonorepoRoot(PathBuf),
     #[non_exhaustive]
     Canonicalize {
         path: PathBuf,
         inner: std::io::Error,
     },
+    #[non_exhaustive]
+    Stdout(std::io::Error),
+}
+
+impl EnumerateErrorKind {
+    /// Classify a failure to spawn `program`, singling out the common setup
+    /// mistake of not having the TypeScript compiler installed.
+    fn spawn(program: &str, err: std::io::Error) -> Self {
+        match err.kind() {
+            std::io::ErrorKind::NotFound => Self::CompilerNotFound {
+                program: program.to_owned(),
+            },
+            _ => Self::Command(err),
+        }
+    }
 }
 
 impl From<string::FromUtf8Error> for EnumerateErrorKind {
@@ -95,57 +134,484 @@ impl From<string::FromUtf8Error> for EnumerateErrorKind {
     }
 }
 
-impl From<path::StripPrefixError> for EnumerateErrorKind {
-    fn from(err: path::StripPrefixError) -> Self {
-        Self::StripPrefix(err)
+/// Remove ANSI escape sequences, like the color codes some tsc versions emit
+/// depending on terminal detection, from a line of compiler output.
+///
+/// Only sequences introduced by an ESC character are removed, so paths that
+/// merely resemble one, like `dist[0m.ts`, are preserved.
+fn strip_ansi_escapes(line: &str) -> Cow<'_, str> {
+    const ESC: char = '\u{1b}';
+    const BEL: char = '\u{7}';
+
+    if !line.contains(ESC) {
+        return Cow::Borrowed(line);
+    }
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != ESC {
+            stripped.push(c);
+            continue;
+        }
+        match chars.next() {
+            // Control sequences, like ESC [ 3 2 m, end with a byte in @ to ~
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // Operating system commands end with BEL or ESC \
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == BEL || (c == ESC && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            // Anything else is a two-character escape
+            _ => {}
+        }
+    }
+    Cow::Owned(stripped)
+}
+
+/// How the compiler is asked to list the files in a compilation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Listing {
+    /// `--listFilesOnly`, which lists the files without compiling them.
+    FilesOnly,
+    /// `--listFiles --noEmit`, for compilers predating `--listFilesOnly`,
+    /// which type-checks the files as it lists them and prints its
+    /// diagnostics among them.
+    Legacy,
+}
+
+impl Listing {
+    fn args(self) -> &'static [&'static str] {
+        match self {
+            Listing::FilesOnly => &["--listFilesOnly"],
+            Listing::Legacy => &["--listFiles", "--noEmit"],
+        }
+    }
+
+    /// Describe the command listing the files of `tsconfig` with `program`.
+    fn command(self, program: &Path, tsconfig: &TypescriptConfigFile) -> String {
+        format!(
+            "{} {} --project {:?}",
+            program.to_string_lossy(),
+            self.args().join(" "),
+            tsconfig
+        )
+    }
+}
+
+/// How a compiler listing the files of a project exited.
+struct CompilerExit {
+    listing: Listing,
+    status: ExitStatus,
+    stderr: Vec<u8>,
+    /// Whether the compiler failed because it does not know `--listFilesOnly`
+    rejected_list_files_only: bool,
+}
+
+/// Whether `output` from the compiler reports `--listFilesOnly` as an unknown
+/// compiler option (error TS5023), as compilers predating it do.
+fn rejects_list_files_only(output: &str) -> bool {
+    output.contains("TS5023") && output.contains("listFilesOnly")
+}
+
+/// Invoke the compiler to list the files of `tsconfig`, passing each listed
+/// monorepo file, as a relative path from the monorepo root, to `visit` as
+/// soon as the compiler reports it. Returns `None` when `visit` breaks, after
+/// killing the compiler so callers that only need part of the output do not
+/// pay for the rest.
+///
+/// Compilers rejecting `--listFilesOnly` are asked again with `--listFiles
+/// --noEmit`, which type-checks the project, so type errors make such
+/// compilers exit with a non-zero status even though they listed every file.
+fn visit_project_files<F>(
+    monorepo_root: &Path,
+    tsconfig: &TypescriptConfigFile,
+    options: &EnumerationOptions,
+    mut visit: F,
+) -> Result<Option<CompilerExit>, EnumerateErrorKind>
+where
+    F: FnMut(PathBuf) -> ControlFlow<()>,
+{
+    match visit_listing(
+        monorepo_root,
+        tsconfig,
+        options,
+        Listing::FilesOnly,
+        &mut visit,
+    )? {
+        Some(exit) if exit.rejected_list_files_only => {
+            debug!("compiler does not support --listFilesOnly, falling back to --listFiles");
+            visit_listing(
+                monorepo_root,
+                tsconfig,
+                options,
+                Listing::Legacy,
+                &mut visit,
+            )
+        }
+        exit => Ok(exit),
+    }
+}
+
+/// Invoke the compiler to list the files of `tsconfig` with `listing`, see
+/// [`visit_project_files`].
+fn visit_listing<F>(
+    monorepo_root: &Path,
+    tsconfig: &TypescriptConfigFile,
+    options: &EnumerationOptions,
+    listing: Listing,
+    visit: &mut F,
+) -> Result<Option<CompilerExit>, EnumerateErrorKind>
+where
+    F: FnMut(PathBuf) -> ControlFlow<()>,
+{
+    let mut compiler = CompilerProcess::spawn(
+        options
+            .compiler_command(monorepo_root)
+            .args(listing.args())
+            // Keep the output plain, whatever the terminal
+            .arg("--pretty")
+            .arg("false")
+            // Name the tsconfig file itself, which need not be tsconfig.json
+            .arg("--project")
+            .arg(monorepo_root.join(tsconfig.as_path())),
+        options.compiler_program(),
+    )?;
+    let flow = compiler.visit_listed_files(monorepo_root, |included_file| {
+        // Diagnostics may name files too, but are never a bare existing path
+        if listing == Listing::Legacy && !monorepo_root.join(&included_file).is_file() {
+            return ControlFlow::Continue(());
+        }
+        visit(included_file)
+    })?;
+    if flow.is_break() {
+        compiler.kill();
+        return Ok(None);
     }
+
+    let rejected_list_files_only = compiler.rejected_list_files_only;
+    let (status, stderr) = compiler.wait()?;
+    let rejected_list_files_only = listing == Listing::FilesOnly
+        && !status.success()
+        && (rejected_list_files_only || rejects_list_files_only(&String::from_utf8_lossy(&stderr)));
+    Ok(Some(CompilerExit {
+        listing,
+        status,
+        stderr,
+        rejected_list_files_only,
+    }))
 }
 
 /// Invoke the TypeScript compiler with the [listFilesOnly] flag to enumerate
 /// the files included in the compilation process.
+///
+/// `monorepo_root` must already be canonical, since tsc reports absolute
+/// paths with symbolic links resolved.
+///
+/// When `options.use_build_info` is set, a fresh `.tsbuildinfo` is consulted
+/// first to avoid spawning tsc at all. Likewise, when
+/// `options.skip_declaration_only_packages` is set, packages including only
+/// `.d.ts` files are estimated instead.
+///
+/// When `options.best_effort` is set, a non-zero tsc exit does not fail
+/// enumeration: the files tsc listed anyway are returned along with its
+/// stderr. The same goes for a compiler falling back to `--listFiles
+/// --noEmit` that listed any files, whatever `options.best_effort`.
 fn tsconfig_includes_exact(
     monorepo_root: &Path,
     tsconfig: &TypescriptConfigFile,
-) -> Result<Vec<PathBuf>, EnumerateError> {
+    options: &EnumerationOptions,
+) -> Result<(Vec<PathBuf>, Option<String>), EnumerateError> {
     (|| {
-        let monorepo_root = std::fs::canonicalize(monorepo_root).map_err(|inner| {
-            EnumerateErrorKind::Canonicalize {
-                path: monorepo_root.to_path_buf(),
-                inner,
+        if options.use_build_info {
+            if let Some(included_files) = tsconfig_includes_from_build_info(monorepo_root, tsconfig)
+            {
+                return Ok((included_files, None));
             }
-        })?;
+            debug!("falling back to tsc, no fresh build info");
+        }
 
-        let child = Command::new("tsc")
-            .arg("--listFilesOnly")
-            .arg("--project")
-            .arg(
-                tsconfig
-                    .package_directory(&monorepo_root)
-                    .map_err(|err| EnumerateErrorKind::PackageInMonorepoRoot(err.0))?,
-            )
-            .output()
-            .map_err(EnumerateErrorKind::Command)?;
-        if child.status.code() != Some(0) {
-            return Err(EnumerateErrorKind::TypescriptCompiler {
-                command: format!("tsc --listFilesOnly --project {:?}", tsconfig),
-                error: child.stderr,
-            });
+        // tsc exits with an error rather than listing no files
+        if is_empty_project(monorepo_root, tsconfig) {
+            debug!("skipping tsc for empty project");
+            return Ok((Vec::new(), None));
         }
-        let stdout = String::from_utf8(child.stdout)?;
 
-        let included_files: Vec<PathBuf> = stdout
-            .lines()
+        if options.skip_declaration_only_packages {
+            if let Some(included_files) = declaration_only_includes(monorepo_root, tsconfig, options) {
+                debug!("skipping tsc for declaration-only package");
+                return Ok((included_files, None));
+            }
+        }
+
+        let program = options.compiler_program();
+        let mut included_files = Vec::new();
+        let CompilerExit {
+            listing,
+            status,
+            stderr,
+            ..
+        } = visit_project_files(monorepo_root, tsconfig, options, |included_file| {
+            included_files.push(included_file);
+            ControlFlow::Continue(())
+        })?
+        .expect("listing every file should never stop early");
+        let diagnostics = match status.code() {
+            Some(0) => None,
+            // Legacy listings type-check the project, so type errors alone
+            // make the compiler exit non-zero after listing every file
+            _ if listing == Listing::Legacy && !included_files.is_empty() => {
+                let stderr = String::from_utf8_lossy(&stderr).into_owned();
+                debug!(?program, ?tsconfig, %stderr, "compiler reported diagnostics after listing files");
+                Some(stderr)
+            }
+            _ if options.best_effort => {
+                let stderr = String::from_utf8_lossy(&stderr).into_owned();
+                warn!(?program, ?tsconfig, %stderr, "compiler exited with non-zero status, using partial output");
+                // A failing tsc may interleave diagnostics with the file list
+                remove_missing_files(monorepo_root, &mut included_files);
+                Some(stderr)
+            }
+            _ => {
+                return Err(EnumerateErrorKind::TypescriptCompiler {
+                    command: listing.command(program, tsconfig),
+                    error: stderr,
+                })
+            }
+        };
+
+        Ok((included_files, diagnostics))
+    })()
+    .map_err(|kind| EnumerateError { kind })
+}
+
+/// Remove the files that do not exist from `included_files`, returning them.
+fn remove_missing_files(monorepo_root: &Path, included_files: &mut Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut missing_files = Vec::new();
+    included_files.retain(|included_file| {
+        let exists = monorepo_root.join(included_file).is_file();
+        if !exists {
+            missing_files.push(included_file.clone());
+        }
+        exists
+    });
+    missing_files
+}
+
+/// A running compiler, whose stdout is read as it is written rather than
+/// buffered whole, since listing a large build graph can print hundreds of
+/// megabytes.
+struct CompilerProcess {
+    child: Child,
+    /// Drains stderr concurrently so the compiler cannot block on a full pipe
+    stderr: JoinHandle<Vec<u8>>,
+    /// Whether the compiler printed that it does not know `--listFilesOnly`
+    rejected_list_files_only: bool,
+}
+
+impl CompilerProcess {
+    fn spawn(command: &mut Command, program: &Path) -> Result<Self, EnumerateErrorKind> {
+        let mut child = command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| EnumerateErrorKind::spawn(&program.to_string_lossy(), err))?;
+        let mut stderr = child.stderr.take().expect("stderr should be piped");
+        let stderr = thread::spawn(move || {
+            let mut buffer = Vec::new();
+            let _ = stderr.read_to_end(&mut buffer);
+            buffer
+        });
+        Ok(Self {
+            child,
+            stderr,
+            rejected_list_files_only: false,
+        })
+    }
+
+    /// Pass each monorepo file the compiler lists, as a relative path from
+    /// the monorepo root, to `visit`, dropping files outside the monorepo or
+    /// inside `node_modules`. Stops reading as soon as `visit` breaks.
+    fn visit_listed_files<F>(
+        &mut self,
+        monorepo_root: &Path,
+        mut visit: F,
+    ) -> Result<ControlFlow<()>, EnumerateErrorKind>
+    where
+        F: FnMut(PathBuf) -> ControlFlow<()>,
+    {
+        let mut stdout = BufReader::new(self.child.stdout.take().expect("stdout should be piped"));
+        // Reuse one line buffer for the whole of the compiler's output
+        let mut buffer = Vec::new();
+        loop {
+            buffer.clear();
+            if stdout
+                .read_until(b'\n', &mut buffer)
+                .map_err(EnumerateErrorKind::Stdout)?
+                == 0
+            {
+                return Ok(ControlFlow::Continue(()));
+            }
+            let line = String::from_utf8(mem::take(&mut buffer))?;
+            // Strip the line ending the way BufRead::lines does
+            let unterminated = match line.strip_suffix('\n') {
+                Some(line) => line.strip_suffix('\r').unwrap_or(line),
+                None => &line,
+            };
+            let stripped = strip_ansi_escapes(unterminated);
+            // tsc prints its diagnostics to stdout
+            self.rejected_list_files_only |= rejects_list_files_only(&stripped);
+            let path = Path::new(stripped.as_ref());
             // Drop the empty newline at the end of stdout
-            .filter(|s| !s.is_empty())
-            .map(PathBuf::from)
-            .filter(|path| is_monorepo_file(&monorepo_root, path))
-            .filter(|path| !is_child_of_node_modules(path))
-            .map(|source_file| {
-                remove_relative_path_prefix_from_absolute_path(&monorepo_root, &source_file)
-            })
-            .collect::<Result<_, _>>()?;
-
-        Ok(included_files)
+            if !path.as_os_str().is_empty() {
+                if let Some(relative_path) = monorepo_relative_path(monorepo_root, path) {
+                    if visit(relative_path.to_owned()).is_break() {
+                        return Ok(ControlFlow::Break(()));
+                    }
+                }
+            }
+            buffer = line.into_bytes();
+        }
+    }
+
+    /// Wait for the compiler to exit, returning its status and stderr.
+    fn wait(mut self) -> Result<(ExitStatus, Vec<u8>), EnumerateErrorKind> {
+        let status = self.child.wait().map_err(EnumerateErrorKind::Command)?;
+        let stderr = self.stderr.join().unwrap_or_default();
+        Ok((status, stderr))
+    }
+
+    /// Stop the compiler without waiting for the rest of its output.
+    fn kill(mut self) {
+        // Killing a process that already exited is not an error worth reporting
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// The parts of a tsconfig file that shape a [build mode] graph.
+///
+/// [build mode]: https://www.typescriptlang.org/docs/handbook/project-references.html#build-mode-for-typescript
+#[derive(Debug, Deserialize)]
+struct ProjectReferences {
+    files: Option<Vec<String>>,
+    include: Option<Vec<String>>,
+    #[serde(default)]
+    references: Vec<ProjectReference>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProjectReference {
+    path: PathBuf,
+}
+
+impl ProjectReferences {
+    /// Whether this is a solution-style tsconfig, which only references
+    /// other projects and has no files of its own.
+    fn is_solution_style(&self) -> bool {
+        self.include.is_none() && self.files.as_ref().is_some_and(Vec::is_empty)
+    }
+}
+
+/// Follow the project references of `solution_tsconfig`, a relative path
+/// from the monorepo root, the way tsc's build mode does, returning every
+/// project in the build graph as a relative path from the monorepo root.
+///
+/// tsc rejects `--listFilesOnly` in build mode (error TS5094), so the files
+/// of a build graph are listed one project at a time instead. Solution-style
+/// projects are left out, since they have no files of their own to list.
+fn build_graph_projects(
+    monorepo_root: &Path,
+    solution_tsconfig: &Path,
+) -> Result<Vec<TypescriptConfigFile>, Error> {
+    let mut projects = Vec::new();
+    let mut visited = HashSet::new();
+    let mut pending = vec![path::normalize(solution_tsconfig)];
+    while let Some(tsconfig) = pending.pop() {
+        if !visited.insert(tsconfig.clone()) {
+            continue;
+        }
+        let project: ProjectReferences = read_json_from_file(monorepo_root.join(&tsconfig))?;
+        let project_directory = tsconfig.parent().unwrap_or_else(|| Path::new(""));
+        // Pushed in reverse, so references are followed in the order listed
+        for reference in project.references.iter().rev() {
+            let referenced = path::normalize(&project_directory.join(&reference.path));
+            // A reference may name the project's directory instead of its tsconfig
+            if monorepo_root.join(&referenced).is_dir() {
+                pending.push(referenced.join("tsconfig.json"));
+            } else {
+                pending.push(referenced);
+            }
+        }
+        if !project.is_solution_style() {
+            projects.push(tsconfig.into());
+        }
+    }
+    Ok(projects)
+}
+
+/// Invoke the TypeScript compiler with the [listFilesOnly] flag, streaming
+/// each included monorepo file, as a relative path from the monorepo root, to
+/// `visit` as soon as tsc reports it.
+///
+/// tsc is killed as soon as `visit` breaks, so callers that only need part of
+/// the output do not pay for the rest.
+///
+/// When `options.best_effort` is set, a non-zero tsc exit does not fail
+/// enumeration, and like with `options.check_files_exist`, listed files
+/// missing from disk are never visited.
+fn visit_tsconfig_includes_exact<F>(
+    monorepo_root: &Path,
+    tsconfig: &TypescriptConfigFile,
+    options: &EnumerationOptions,
+    mut visit: F,
+) -> Result<(), EnumerateError>
+where
+    F: FnMut(PathBuf) -> ControlFlow<()>,
+{
+    (|| {
+        // A failing tsc may interleave diagnostics with the file list
+        let skip_missing_files = options.best_effort || options.check_files_exist;
+        let mut listed_any_file = false;
+        let Some(CompilerExit {
+            listing,
+            status,
+            stderr,
+            ..
+        }) = visit_project_files(monorepo_root, tsconfig, options, |included_file| {
+            if skip_missing_files && !monorepo_root.join(&included_file).is_file() {
+                return ControlFlow::Continue(());
+            }
+            listed_any_file = true;
+            visit(included_file)
+        })?
+        else {
+            return Ok(());
+        };
+        match status.code() {
+            Some(0) => {}
+            // Type errors alone make legacy listings exit non-zero
+            _ if listing == Listing::Legacy && listed_any_file => {}
+            _ if options.best_effort => {
+                let stderr = String::from_utf8_lossy(&stderr);
+                warn!(program = ?options.compiler_program(), ?tsconfig, %stderr, "compiler exited with non-zero status, using partial output");
+            }
+            _ => {
+                return Err(EnumerateErrorKind::TypescriptCompiler {
+                    command: listing.command(options.compiler_program(), tsconfig),
+                    error: stderr,
+                })
+            }
+        }
+        Ok(())
     })()
     .map_err(|kind| EnumerateError { kind })
 }
@@ -156,12 +622,44 @@ pub struct Error {
     kind: ErrorKind,
 }
 
+impl Error {
+    /// What went wrong, for callers that need to tell failures apart.
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+}
+
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self.kind {
             ErrorKind::PackageInMonorepoRoot(path) => {
                 write!(f, "unexpected package in monorepo root: {:?}", path)
             }
+            ErrorKind::DuplicatePackageName { name, paths } => write!(
+                f,
+                "packages {:?} and {:?} both declare the name {}",
+                paths[0], paths[1], name
+            ),
+            ErrorKind::MissingInternalDependency { dependent, missing } => write!(
+                f,
+                "package {} depends on {}, which is not a package in the monorepo",
+                dependent, missing
+            ),
+            ErrorKind::MissingPackageName(path) => {
+                write!(
+                    f,
+                    "package manifest {:?} does not declare a string name",
+                    path
+                )
+            }
+            ErrorKind::PackageNotInMonorepo(path) => write!(
+                f,
+                "tsconfig {:?} does not belong to a package in the monorepo",
+                path
+            ),
+            ErrorKind::Canonicalize { path, inner: _ } => {
+                write!(f, "unable to canonicalize path {:?}", path)
+            }
             _ => write!(f, "unable to enumerate exact tsconfig includes"),
         }
     }
@@ -172,9 +670,17 @@ impl std::error::Error for Error {
         match &self.kind {
             ErrorKind::MonorepoManifest(err) => Some(err),
             ErrorKind::EnumeratePackageManifestsError(err) => Some(err),
+            ErrorKind::DuplicatePackageName { name: _, paths: _ } => None,
+            ErrorKind::MissingInternalDependency {
+                dependent: _,
+                missing: _,
+            } => None,
             ErrorKind::PackageInMonorepoRoot(_) => None,
+            ErrorKind::MissingPackageName(_) => None,
+            ErrorKind::PackageNotInMonorepo(_) => None,
             ErrorKind::FromFile(err) => Some(err),
             ErrorKind::Enumerate(err) => Some(err),
+            ErrorKind::Canonicalize { path: _, inner } => Some(inner),
         }
     }
 }
@@ -201,6 +707,18 @@ impl From<typescript_tools::monorepo_manifest::EnumeratePackageManifestsError> f
     }
 }
 
+impl From<PackageManifestsError> for Error {
+    fn from(err: PackageManifestsError) -> Self {
+        let kind = match err {
+            PackageManifestsError::Enumerate(err) => ErrorKind::EnumeratePackageManifestsError(err),
+            PackageManifestsError::DuplicatePackageName { name, paths } => {
+                ErrorKind::DuplicatePackageName { name, paths }
+            }
+        };
+        Self { kind }
+    }
+}
+
 impl From<crate::io::FromFileError> for Error {
     fn from(err: crate::io::FromFileError) -> Self {
         Self {
@@ -224,6 +742,15 @@ impl From<FromTypescriptConfigFileError> for Error {
                 ErrorKind::PackageInMonorepoRoot(path)
             }
             FromTypescriptConfigFileError::FromFile(err) => ErrorKind::FromFile(err),
+            FromTypescriptConfigFileError::MissingPackageName(path) => {
+                ErrorKind::MissingPackageName(path)
+            }
+            FromTypescriptConfigFileError::PackageNotInMonorepo(path) => {
+                ErrorKind::PackageNotInMonorepo(path)
+            }
+            FromTypescriptConfigFileError::RootPackageManifest(err) => {
+                ErrorKind::MonorepoManifest(err)
+            }
         };
         Self { kind }
     }
@@ -246,11 +773,36 @@ pub enum ErrorKind {
         typescript_tools::monorepo_manifest::EnumeratePackageManifestsError,
     ),
     #[non_exhaustive]
+    DuplicatePackageName { name: String, paths: [PathBuf; 2] },
+    #[non_exhaustive]
+    MissingInternalDependency { dependent: String, missing: String },
+    #[non_exhaustive]
     PackageInMonorepoRoot(PathBuf),
     #[non_exhaustive]
+    MissingPackageName(PathBuf),
+    #[non_exhaustive]
+    PackageNotInMonorepo(PathBuf),
+    #[non_exhaustive]
     FromFile(crate::io::FromFileError),
     #[non_exhaustive]
     Enumerate(EnumerateError),
+    #[non_exhaustive]
+    Canonicalize {
+        path: PathBuf,
+        inner: std::io::Error,
+    },
+}
+
+/// Canonicalize `monorepo_root` once, so that manifest lookups, tsconfig
+/// reads, and the stripping of tsc's absolute output paths all agree on the
+/// same root even when the caller passes a relative path.
+fn canonicalize_monorepo_root(monorepo_root: &Path) -> Result<PathBuf, Error> {
+    std::fs::canonicalize(monorepo_root).map_err(|inner| Error {
+        kind: ErrorKind::Canonicalize {
+            path: monorepo_root.to_path_buf(),
+            inner,
+        },
+    })
 }
 
 /// Enumerate source code files used by the TypeScript compiler during
@@ -268,78 +820,499 @@ where
     Q: IntoIterator,
     Q::Item: AsRef<Path>,
 {
-    let lerna_manifest =
-        monorepo_manifest::MonorepoManifest::from_directory(monorepo_root.as_ref())
-            .map_err(|thing| thing)?;
-    let package_manifests_by_package_name = lerna_manifest.package_manifests_by_package_name()?;
-    trace!("{:?}", lerna_manifest);
+    tsconfig_includes_by_package_name_with_options(
+        monorepo_root,
+        tsconfig_files,
+        &EnumerationOptions::default(),
+    )
+}
 
-    // As relative path from monorepo root
-    let transitive_internal_dependency_tsconfigs_inclusive_to_enumerate: HashSet<
-        TypescriptPackage,
-    > = tsconfig_files
+/// Enumerate source code files used by the TypeScript compiler during
+/// compilation. The return value is a list of alphabetically-sorted relative
+/// paths from the monorepo root, grouped by the relative path from the
+/// monorepo root to the tsconfig file that includes them.
+///
+/// - `monorepo_root` may be an absolute path
+/// - `tsconfig_files` should be relative paths from the monorepo root
+pub fn tsconfig_includes_by_tsconfig<P, Q>(
+    monorepo_root: P,
+    tsconfig_files: Q,
+) -> Result<HashMap<PathBuf, Vec<PathBuf>>, Error>
+where
+    P: AsRef<Path> + Sync,
+    Q: IntoIterator,
+    Q::Item: AsRef<Path>,
+{
+    tsconfig_includes_by_tsconfig_with_options(
+        monorepo_root,
+        tsconfig_files,
+        &EnumerationOptions::default(),
+    )
+}
+
+/// Like [`tsconfig_includes_by_package_name`], but read each package's file
+/// list from its `.tsbuildinfo` when one exists and is newer than all of the
+/// package's source files, instead of invoking the TypeScript compiler.
+///
+/// Packages with a missing, stale, or unparseable `.tsbuildinfo` fall back to
+/// a real `tsc --listFilesOnly` invocation. Note that the `.tsbuildinfo`
+/// format is specific to the version of tsc that wrote it.
+pub fn tsconfig_includes_by_package_name_incremental<P, Q>(
+    monorepo_root: P,
+    tsconfig_files: Q,
+) -> Result<HashMap<String, Vec<PathBuf>>, Error>
+where
+    P: AsRef<Path> + Sync,
+    Q: IntoIterator,
+    Q::Item: AsRef<Path>,
+{
+    tsconfig_includes_by_package_name_with_options(
+        monorepo_root,
+        tsconfig_files,
+        &EnumerationOptions::default().use_build_info(true),
+    )
+}
+
+/// Like [`tsconfig_includes_by_package_name`], but enumerate a whole
+/// composite build graph rooted at `solution_tsconfig`, instead of the
+/// requested packages and their internal dependencies. The project
+/// references are followed the way `tsc --build` follows them, so every
+/// referenced project is enumerated, each with its own `tsc --listFilesOnly`
+/// invocation since build mode rejects that flag.
+///
+/// Referencing projects list the files of their references too, so each
+/// file is attributed to the package whose directory contains it, not to the
+/// project that listed it. When package directories are nested, the file goes to the deepest
+/// one. In particular, a file under a directory shared by two referenced
+/// projects, like a `shared/` directory outside of both, belongs to whichever
+/// package directory most closely contains it, and is omitted when no
+/// package directory does.
+///
+/// - `monorepo_root` may be an absolute path
+/// - `solution_tsconfig` should be a relative path from the monorepo root
+pub fn tsconfig_includes_by_package_name_build<P, Q>(
+    monorepo_root: P,
+    solution_tsconfig: Q,
+) -> Result<HashMap<String, Vec<PathBuf>>, Error>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    tsconfig_includes_by_package_name_build_with_options(
+        monorepo_root,
+        solution_tsconfig,
+        &EnumerationOptions::default(),
+    )
+}
+
+/// Determine whether the TypeScript compiler includes `file` when compiling
+/// `tsconfig_file`, stopping as soon as tsc reports the file.
+///
+/// - `monorepo_root` may be an absolute path
+/// - `tsconfig_file` and `file` should be relative paths from the monorepo root
+pub fn package_includes_file<P, Q, R>(
+    monorepo_root: P,
+    tsconfig_file: Q,
+    file: R,
+) -> Result<bool, Error>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+    R: AsRef<Path>,
+{
+    package_includes_file_with_options(
+        monorepo_root,
+        tsconfig_file,
+        file,
+        &EnumerationOptions::default(),
+    )
+}
+
+/// Count the source code files used by the TypeScript compiler during
+/// compilation, grouped by scoped package name.
+///
+/// tsc's output is counted as it streams in, so this never holds the full
+/// list of paths for a package in memory.
+///
+/// - `monorepo_root` may be an absolute path
+/// - `tsconfig_files` should be relative paths from the monorepo root
+pub fn tsconfig_include_counts_by_package_name<P, Q>(
+    monorepo_root: P,
+    tsconfig_files: Q,
+) -> Result<HashMap<String, usize>, Error>
+where
+    P: AsRef<Path>,
+    Q: IntoIterator,
+    Q::Item: AsRef<Path>,
+{
+    tsconfig_include_counts_by_package_name_with_options(
+        monorepo_root,
+        tsconfig_files,
+        &EnumerationOptions::default(),
+    )
+}
+
+pub(crate) fn package_includes_file_with_options<P, Q, R>(
+    monorepo_root: P,
+    tsconfig_file: Q,
+    file: R,
+    options: &EnumerationOptions,
+) -> Result<bool, Error>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+    R: AsRef<Path>,
+{
+    let monorepo_root = canonicalize_monorepo_root(monorepo_root.as_ref())?;
+    let tsconfig_file: TypescriptConfigFile = tsconfig_file.as_ref().into();
+    let file = path::normalize(file.as_ref());
+    let mut includes_file = false;
+    visit_tsconfig_includes_exact(&monorepo_root, &tsconfig_file, options, |included_file| {
+        if included_file == file {
+            includes_file = true;
+            return ControlFlow::Break(());
+        }
+        ControlFlow::Continue(())
+    })?;
+    Ok(includes_file)
+}
+
+pub(crate) fn tsconfig_include_counts_by_package_name_with_options<P, Q>(
+    monorepo_root: P,
+    tsconfig_files: Q,
+    options: &EnumerationOptions,
+) -> Result<HashMap<String, usize>, Error>
+where
+    P: AsRef<Path>,
+    Q: IntoIterator,
+    Q::Item: AsRef<Path>,
+{
+    let monorepo_root = canonicalize_monorepo_root(monorepo_root.as_ref())?;
+    let typescript_packages =
+        resolve_typescript_packages(&monorepo_root, tsconfig_files, options)?.typescript_packages;
+    count_by_package_name(typescript_packages, |typescript_package, count| {
+        visit_tsconfig_includes_exact(
+            &monorepo_root,
+            &typescript_package.tsconfig_file,
+            options,
+            |included_file| {
+                count(included_file);
+                ControlFlow::Continue(())
+            },
+        )
+        .map_err(Error::from)
+    })
+}
+
+pub(crate) fn tsconfig_includes_by_package_name_with_options<P, Q>(
+    monorepo_root: P,
+    tsconfig_files: Q,
+    options: &EnumerationOptions,
+) -> Result<HashMap<String, Vec<PathBuf>>, Error>
+where
+    P: AsRef<Path> + Sync,
+    Q: IntoIterator,
+    Q::Item: AsRef<Path>,
+{
+    let included_files = enumerate(monorepo_root, tsconfig_files, options)?;
+    Ok(group_by_package_name(
+        included_files,
+        options.preserve_compile_order,
+    ))
+}
+
+pub(crate) fn tsconfig_includes_by_package_name_build_with_options<P, Q>(
+    monorepo_root: P,
+    solution_tsconfig: Q,
+    options: &EnumerationOptions,
+) -> Result<HashMap<String, Vec<PathBuf>>, Error>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let included_files = enumerate_build(monorepo_root, solution_tsconfig, options)?.included_files;
+    Ok(group_by_package_name(
+        included_files,
+        options.preserve_compile_order,
+    ))
+}
+
+/// Enumerate every project in the build graph rooted at `solution_tsconfig`,
+/// attributing each listed file to the package whose directory contains it,
+/// along with a [`Warning`] for each project whose files were salvaged from a
+/// failing tsc in best-effort mode and for each listed file found missing
+/// when checking that files exist.
+pub(crate) fn enumerate_build<P, Q>(
+    monorepo_root: P,
+    solution_tsconfig: Q,
+    options: &EnumerationOptions,
+) -> Result<Enumeration, Error>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let monorepo_root = canonicalize_monorepo_root(monorepo_root.as_ref())?;
+    let lerna_manifest = monorepo_manifest::MonorepoManifest::from_directory(&monorepo_root)?;
+    let package_manifests_by_package_name = package_manifests_by_package_name(&lerna_manifest)?;
+
+    let mut typescript_packages = package_manifests_by_package_name
         .into_iter()
-        .map(|tsconfig_file| -> Result<Vec<TypescriptPackage>, Error> {
+        .map(|(package_name, package_manifest)| {
             let tsconfig_file: TypescriptConfigFile =
-                monorepo_root.as_ref().join(tsconfig_file.as_ref()).into();
-            let package_manifest: PackageManifest = (&tsconfig_file).try_into()?;
-
-            let package_manifest = package_manifests_by_package_name
-                .get(&package_manifest.name)
-                .expect(&format!(
-                    "tsconfig {:?} should belong to a package in the lerna monorepo",
-                    tsconfig_file
-                ));
-
-            let transitive_internal_dependencies_inclusive = {
-                // Enumerate internal dependencies (exclusive)
-                package_manifest
-                    .transitive_internal_dependency_package_names_exclusive(
-                        &package_manifests_by_package_name,
-                    )
-                    // Make this list inclusive of the target package
-                    .chain(iter::once(package_manifest))
-            };
+                PackageManifestFile::from(package_manifest.path()).try_into()?;
+            Ok(TypescriptPackage::beside_package_manifest(
+                &monorepo_root,
+                package_name,
+                tsconfig_file,
+            ))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    // Deepest package directories first, so nested packages claim their files
+    typescript_packages.sort_by_key(|typescript_package| {
+        Reverse(
+            typescript_package
+                .tsconfig_file
+                .as_path()
+                .components()
+                .count(),
+        )
+    });
 
-            Ok(transitive_internal_dependencies_inclusive
-                .map(
-                    |package_manifest| -> Result<_, PackageInMonorepoRootError> {
-                        let package_manifest_file =
-                            PackageManifestFile::from(package_manifest.path());
-                        let tsconfig_file: TypescriptConfigFile =
-                            package_manifest_file.try_into()?;
-                        let typescript_package = TypescriptPackage {
-                            scoped_package_name: package_manifest.contents.name.clone(),
-                            tsconfig_file,
-                        };
-                        Ok(typescript_package)
-                    },
-                )
-                .collect::<Result<_, _>>()?)
+    let _span = debug_span!(
+        "enumerate_solution",
+        tsconfig = ?solution_tsconfig.as_ref(),
+        calculation = "exact",
+    )
+    .entered();
+    let projects = build_graph_projects(&monorepo_root, solution_tsconfig.as_ref())?;
+    debug!(projects = projects.len(), "resolved build graph");
+    let listed_projects: Vec<(TypescriptConfigFile, Vec<PathBuf>, Option<String>)> = projects
+        .into_par_iter()
+        .map(|tsconfig| {
+            let (listed_files, diagnostics) =
+                tsconfig_includes_exact(&monorepo_root, &tsconfig, options)?;
+            Ok((tsconfig, listed_files, diagnostics))
+        })
+        .collect::<Result<_, EnumerateError>>()?;
+
+    let owner = |file: &Path| {
+        typescript_packages.iter().position(|typescript_package| {
+            typescript_package
+                .tsconfig_file
+                .as_path()
+                .parent()
+                .is_some_and(|package_directory| file.starts_with(package_directory))
         })
-        // REFACTOR: avoid intermediate allocations
-        .collect::<Result<Vec<_>, _>>()?
+    };
+    let mut warnings = Vec::new();
+    let mut seen_files = HashSet::new();
+    let mut included_files: Vec<Vec<PathBuf>> = vec![Vec::new(); typescript_packages.len()];
+    for (tsconfig, mut listed_files, diagnostics) in listed_projects {
+        if let Some(stderr) = diagnostics {
+            match owner(tsconfig.as_path()) {
+                Some(index) => warnings.push(Warning::CompilerDiagnostics {
+                    package: typescript_packages[index].scoped_package_name.clone(),
+                    stderr,
+                }),
+                None => trace!(
+                    ?tsconfig,
+                    "omitting diagnostics outside every package directory"
+                ),
+            }
+        }
+        let missing_files = if options.check_files_exist {
+            remove_missing_files(&monorepo_root, &mut listed_files)
+        } else {
+            Vec::new()
+        };
+        for file in missing_files {
+            match owner(&file) {
+                Some(index) => warnings.push(Warning::MissingEnumeratedFile {
+                    package: typescript_packages[index].scoped_package_name.clone(),
+                    file,
+                }),
+                None => trace!(
+                    ?file,
+                    "omitting missing file outside every package directory"
+                ),
+            }
+        }
+        // Referencing projects list the files of their references again
+        for file in listed_files {
+            if !seen_files.insert(file.clone()) {
+                continue;
+            }
+            match owner(&file) {
+                Some(index) => included_files[index].push(file),
+                None => trace!(?file, "omitting file outside every package directory"),
+            }
+        }
+    }
+
+    let included_files = typescript_packages
         .into_iter()
-        .flatten()
+        .zip(included_files)
+        .filter(|(_, included_files)| !included_files.is_empty())
+        .map(|(typescript_package, included_files)| {
+            let mut included_files = options.emitted_files(&typescript_package, included_files);
+            if !options.preserve_compile_order {
+                included_files.sort_unstable();
+            }
+            (typescript_package, included_files)
+        })
         .collect();
+    Ok(Enumeration {
+        included_files,
+        warnings,
+        ..Enumeration::default()
+    })
+}
+
+pub(crate) fn tsconfig_includes_by_tsconfig_with_options<P, Q>(
+    monorepo_root: P,
+    tsconfig_files: Q,
+    options: &EnumerationOptions,
+) -> Result<HashMap<PathBuf, Vec<PathBuf>>, Error>
+where
+    P: AsRef<Path> + Sync,
+    Q: IntoIterator,
+    Q::Item: AsRef<Path>,
+{
+    let included_files = enumerate(monorepo_root, tsconfig_files, options)?;
+    Ok(group_by_tsconfig(included_files))
+}
 
+/// Resolve the requested tsconfig files, along with the tsconfig files of
+/// their internal dependencies up to `dependency_depth`, against the
+/// monorepo manifest.
+fn resolve_typescript_packages<Q>(
+    monorepo_root: &Path,
+    tsconfig_files: Q,
+    options: &EnumerationOptions,
+) -> Result<ResolvedPackages, Error>
+where
+    Q: IntoIterator,
+    Q::Item: AsRef<Path>,
+{
+    let lerna_manifest = monorepo_manifest::MonorepoManifest::from_directory(monorepo_root)?;
+    let package_manifests_by_package_name = package_manifests_by_package_name(&lerna_manifest)?;
+    trace!(?lerna_manifest, "read monorepo manifest");
+
+    let resolved_packages = typescript_packages_to_enumerate(
+        monorepo_root,
+        tsconfig_files,
+        &package_manifests_by_package_name,
+        options,
+    )?;
+    if !options.allow_missing_internal_dependencies {
+        if let Some((dependent, missing)) = resolved_packages.missing_internal_dependencies.first()
+        {
+            return Err(Error {
+                kind: ErrorKind::MissingInternalDependency {
+                    dependent: dependent.clone(),
+                    missing: missing.clone(),
+                },
+            });
+        }
+    }
     debug!(
-        "transitive_internal_dependency_tsconfigs_inclusive_to_enumerate: {:?}",
-        transitive_internal_dependency_tsconfigs_inclusive_to_enumerate
+        packages = resolved_packages.typescript_packages.len(),
+        without_tsconfig = resolved_packages.packages_without_tsconfig.len(),
+        "resolved packages to enumerate"
     );
+    Ok(resolved_packages)
+}
 
-    let included_files: HashMap<String, Vec<PathBuf>> =
-        transitive_internal_dependency_tsconfigs_inclusive_to_enumerate
-            .into_par_iter()
-            .map(|typescript_package| -> Result<(_, _), Error> {
-                // This relies on the assumption that tsconfig.json is always the name of the tsconfig file
-                let tsconfig = &typescript_package.tsconfig_file;
-                let mut included_files = tsconfig_includes_exact(monorepo_root.as_ref(), tsconfig)?;
+pub(crate) fn enumerate<P, Q>(
+    monorepo_root: P,
+    tsconfig_files: Q,
+    options: &EnumerationOptions,
+) -> Result<Vec<(TypescriptPackage, Vec<PathBuf>)>, Error>
+where
+    P: AsRef<Path> + Sync,
+    Q: IntoIterator,
+    Q::Item: AsRef<Path>,
+{
+    Ok(enumerate_detailed(monorepo_root, tsconfig_files, options)?.included_files)
+}
+
+/// Like [`enumerate`], but also return a [`Warning`] for each package whose
+/// files were salvaged from a failing tsc in best-effort mode, for each
+/// listed file found missing when checking that files exist, and the
+/// internal dependencies skipped for lacking a tsconfig.
+pub(crate) fn enumerate_detailed<P, Q>(
+    monorepo_root: P,
+    tsconfig_files: Q,
+    options: &EnumerationOptions,
+) -> Result<Enumeration, Error>
+where
+    P: AsRef<Path> + Sync,
+    Q: IntoIterator,
+    Q::Item: AsRef<Path>,
+{
+    let monorepo_root = canonicalize_monorepo_root(monorepo_root.as_ref())?;
+    // As relative path from monorepo root
+    let resolved_packages = resolve_typescript_packages(&monorepo_root, tsconfig_files, options)?;
+    let mut warnings: Vec<Warning> = resolved_packages
+        .missing_internal_dependency_warnings()
+        .collect();
+    let ResolvedPackages {
+        typescript_packages,
+        packages_without_tsconfig,
+        missing_internal_dependencies: _,
+        dependency_graph,
+    } = resolved_packages;
+
+    let enumerated: Vec<(TypescriptPackage, Vec<PathBuf>, Vec<Warning>)> = typescript_packages
+        .into_par_iter()
+        .map(|typescript_package| -> Result<(_, _, _), Error> {
+            let _span = debug_span!(
+                "enumerate_package",
+                package = %typescript_package.scoped_package_name,
+                tsconfig = ?typescript_package.tsconfig_file.as_path(),
+                calculation = "exact",
+            )
+            .entered();
+            let tsconfig = &typescript_package.tsconfig_file;
+            let (mut included_files, diagnostics) =
+                tsconfig_includes_exact(&monorepo_root, tsconfig, options)?;
+            let mut package_warnings: Vec<Warning> = diagnostics
+                .map(|stderr| Warning::CompilerDiagnostics {
+                    package: typescript_package.scoped_package_name.clone(),
+                    stderr,
+                })
+                .into_iter()
+                .collect();
+            if options.check_files_exist {
+                package_warnings.extend(
+                    remove_missing_files(&monorepo_root, &mut included_files)
+                        .into_iter()
+                        .map(|file| Warning::MissingEnumeratedFile {
+                            package: typescript_package.scoped_package_name.clone(),
+                            file,
+                        }),
+                );
+            }
+            let mut included_files = options.emitted_files(&typescript_package, included_files);
+            if !options.preserve_compile_order {
                 included_files.sort_unstable();
-                Ok((typescript_package.scoped_package_name, included_files))
-            })
-            .collect::<Result<HashMap<_, _>, _>>()?;
+            }
+            debug!(files = included_files.len(), "enumerated package");
+            Ok((typescript_package, included_files, package_warnings))
+        })
+        .collect::<Result<_, _>>()?;
 
-    debug!("tsconfig_includes: {:?}", included_files);
-    Ok(included_files)
+    let included_files = enumerated
+        .into_iter()
+        .map(|(typescript_package, included_files, package_warnings)| {
+            warnings.extend(package_warnings);
+            (typescript_package, included_files)
+        })
+        .collect();
+    Ok(Enumeration {
+        included_files,
+        warnings,
+        packages_without_tsconfig,
+        dependency_graph,
+    })
 }
@@ -0,0 +1,167 @@
+//! Resolution of the tsconfig `extends` property.
+//!
+//! Relative specifiers resolve against the directory of the extending
+//! tsconfig. Bare specifiers, like `@tsconfig/node18/tsconfig.json`, resolve
+//! through Node module resolution: each `node_modules` directory from the
+//! extending tsconfig's directory up to the filesystem root is searched for
+//! the named package, honoring the package's `exports` map and the
+//! `tsconfig` field of its manifest.
+
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{io::read_json_from_file, path::normalize};
+
+/// Conditions tried, in order, when an `exports` entry is a conditional object.
+const EXPORTS_CONDITIONS: [&str; 4] = ["types", "require", "import", "default"];
+
+/// The `extends` property, which TypeScript 5.0 and later also accept as a
+/// list of configurations applied in order.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum Extends {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl Extends {
+    pub(crate) fn specifiers(&self) -> &[String] {
+        match self {
+            Extends::Single(specifier) => std::slice::from_ref(specifier),
+            Extends::Multiple(specifiers) => specifiers,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PackageManifest {
+    exports: Option<Value>,
+    tsconfig: Option<String>,
+    main: Option<String>,
+}
+
+fn is_relative_specifier(specifier: &str) -> bool {
+    specifier.starts_with("./")
+        || specifier.starts_with("../")
+        || specifier == "."
+        || specifier == ".."
+        || Path::new(specifier).is_absolute()
+}
+
+/// Find `path` on disk, trying it with a `.json` extension when it names
+/// nothing.
+fn existing_file(path: PathBuf) -> Option<PathBuf> {
+    if path.is_file() {
+        return Some(path);
+    }
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        return None;
+    }
+    let mut with_extension = path.into_os_string();
+    with_extension.push(".json");
+    Some(PathBuf::from(with_extension)).filter(|path| path.is_file())
+}
+
+/// Resolve one `exports` target, which is either a path or a set of
+/// conditional targets.
+fn resolve_exports_target(target: &Value) -> Option<&str> {
+    match target {
+        Value::String(target) => Some(target),
+        Value::Object(conditions) => EXPORTS_CONDITIONS
+            .iter()
+            .filter_map(|condition| conditions.get(*condition))
+            .find_map(resolve_exports_target),
+        Value::Array(targets) => targets.iter().find_map(resolve_exports_target),
+        _ => None,
+    }
+}
+
+/// Look up `subpath` (like `.` or `./base.json`) in a package's `exports`.
+fn resolve_exports(exports: &Value, subpath: &str) -> Option<String> {
+    let is_subpath_map = matches!(
+        exports,
+        Value::Object(entries) if entries.keys().all(|key| key.starts_with('.'))
+    );
+    if !is_subpath_map {
+        return (subpath == ".")
+            .then(|| resolve_exports_target(exports))
+            .flatten()
+            .map(ToOwned::to_owned);
+    }
+    let entries = exports.as_object()?;
+    if let Some(target) = entries.get(subpath) {
+        return resolve_exports_target(target).map(ToOwned::to_owned);
+    }
+    // Subpath patterns, like "./*": "./configs/*.json"
+    entries.iter().find_map(|(key, target)| {
+        let (prefix, suffix) = key.split_once('*')?;
+        let matched = subpath.strip_prefix(prefix)?.strip_suffix(suffix)?;
+        resolve_exports_target(target).map(|target| target.replace('*', matched))
+    })
+}
+
+/// Resolve `subpath` within the package installed at `package_directory`.
+fn resolve_in_package(package_directory: &Path, subpath: &str) -> Option<PathBuf> {
+    let manifest: PackageManifest =
+        read_json_from_file(package_directory.join("package.json")).unwrap_or_default();
+
+    let exports_subpath = match subpath {
+        "" => String::from("."),
+        subpath => format!("./{}", subpath),
+    };
+    if let Some(exports) = &manifest.exports {
+        if let Some(target) = resolve_exports(exports, &exports_subpath) {
+            return existing_file(package_directory.join(target));
+        }
+    }
+
+    if !subpath.is_empty() {
+        return existing_file(package_directory.join(subpath));
+    }
+    manifest
+        .tsconfig
+        .into_iter()
+        .chain(manifest.main.filter(|main| main.ends_with(".json")))
+        .chain(Some(String::from("tsconfig.json")))
+        .find_map(|candidate| existing_file(package_directory.join(candidate)))
+}
+
+/// Split a bare specifier into its package name and the subpath within the
+/// package.
+fn split_bare_specifier(specifier: &str) -> Option<(&str, &str)> {
+    let separator = if specifier.starts_with('@') {
+        let scope_end = specifier.find('/')?;
+        specifier[scope_end + 1..]
+            .find('/')
+            .map(|index| scope_end + 1 + index)
+    } else {
+        specifier.find('/')
+    };
+    Some(match separator {
+        Some(index) => (&specifier[..index], &specifier[index + 1..]),
+        None => (specifier, ""),
+    })
+}
+
+/// Resolve an `extends` specifier found in a tsconfig located in
+/// `config_directory` to the path of the extended tsconfig file.
+pub(crate) fn resolve_extends(config_directory: &Path, specifier: &str) -> Option<PathBuf> {
+    if is_relative_specifier(specifier) {
+        return existing_file(normalize(&config_directory.join(specifier)));
+    }
+
+    let (package_name, subpath) = split_bare_specifier(specifier)?;
+    let config_directory = std::fs::canonicalize(config_directory).ok()?;
+    config_directory.ancestors().find_map(|directory| {
+        let package_directory = directory.join("node_modules").join(package_name);
+        if !package_directory.is_dir() {
+            return None;
+        }
+        resolve_in_package(&package_directory, subpath)
+    })
+}
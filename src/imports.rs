@@ -0,0 +1,256 @@
+//! Follow `import` statements in estimate mode.
+//!
+//! Module specifiers are found with a lexical scan rather than a parse, so
+//! this is a best effort: specifiers appearing inside string literals or
+//! template strings may be picked up, and specifiers built at runtime are
+//! missed. Only relative specifiers and, when enabled, specifiers matching
+//! the tsconfig's `paths` or `baseUrl` resolve; bare package specifiers
+//! resolve into `node_modules`, which is never enumerated.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use tracing::debug;
+
+use crate::path::{is_child_of_node_modules, normalize};
+
+/// Keywords after which a module specifier may appear.
+const SPECIFIER_KEYWORDS: [&str; 3] = ["from", "import", "require"];
+
+/// How import specifiers resolve to files, derived from a package's
+/// tsconfig.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ModuleResolution {
+    /// Extensions tried, in order, when a specifier omits one.
+    pub extensions: Vec<&'static str>,
+    /// Whether imports of arbitrary extensions, like `./styles.css`, resolve
+    /// to declaration shims like `./styles.d.css.ts`.
+    pub allow_arbitrary_extensions: bool,
+    /// Directory non-relative specifiers resolve against, from
+    /// `compilerOptions.baseUrl`.
+    pub base_url: Option<PathBuf>,
+    /// `compilerOptions.paths` patterns mapped to their substitutions, along
+    /// with the directory substitutions resolve against.
+    pub paths: Option<(PathBuf, BTreeMap<String, Vec<String>>)>,
+}
+
+/// Remove line and block comments so that commented-out imports are not
+/// followed.
+fn strip_comments(contents: &str) -> String {
+    let mut stripped = String::with_capacity(contents.len());
+    let mut rest = contents;
+    while let Some(index) = rest.find('/') {
+        stripped.push_str(&rest[..index]);
+        let after = &rest[index..];
+        if after.starts_with("//") {
+            rest = after.find('\n').map_or("", |end| &after[end..]);
+        } else if let Some(comment) = after.strip_prefix("/*") {
+            rest = comment.find("*/").map_or("", |end| &comment[end + 2..]);
+        } else {
+            stripped.push('/');
+            rest = &after[1..];
+        }
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+/// Find the module specifiers of `import`, `export ... from`, dynamic
+/// `import()`, and `require()` in `contents`.
+pub(crate) fn import_specifiers(contents: &str) -> Vec<String> {
+    let contents = strip_comments(contents);
+    let is_identifier = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+
+    let mut specifiers = Vec::new();
+    for keyword in SPECIFIER_KEYWORDS {
+        for (index, _) in contents.match_indices(keyword) {
+            let preceded_by_identifier = contents[..index]
+                .chars()
+                .next_back()
+                .is_some_and(|c| is_identifier(c) || c == '.');
+            if preceded_by_identifier {
+                continue;
+            }
+            let after = &contents[index + keyword.len()..];
+            if after.starts_with(is_identifier) {
+                continue;
+            }
+            let after = after.trim_start();
+            let after = after.strip_prefix('(').unwrap_or(after).trim_start();
+            let Some(quote) = after.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+                continue;
+            };
+            let value = &after[1..];
+            if let Some(end) = value.find(quote) {
+                specifiers.push(value[..end].to_owned());
+            }
+        }
+    }
+    specifiers
+}
+
+fn is_relative_specifier(specifier: &str) -> bool {
+    specifier == "."
+        || specifier == ".."
+        || specifier.starts_with("./")
+        || specifier.starts_with("../")
+}
+
+impl ModuleResolution {
+    /// Resolve `candidate` the way tsc resolves a module path: as a file, as
+    /// a file with one of the supported extensions, or as a directory with
+    /// an index file.
+    fn resolve_module_path(&self, candidate: &Path) -> Option<PathBuf> {
+        let with_suffix = |path: &Path, suffix: &str| {
+            let mut path = path.as_os_str().to_owned();
+            path.push(suffix);
+            PathBuf::from(path)
+        };
+        let has_supported_extension = candidate.to_str().is_some_and(|path| {
+            path.ends_with(".json")
+                || self
+                    .extensions
+                    .iter()
+                    .any(|extension| path.ends_with(extension))
+        });
+        if has_supported_extension && candidate.is_file() {
+            return Some(candidate.to_owned());
+        }
+        // TypeScript sources are imported by the name of their emitted output
+        if let Some(stem) = candidate.to_str().and_then(|path| {
+            path.strip_suffix(".js")
+                .or_else(|| path.strip_suffix(".jsx"))
+        }) {
+            if let Some(source) = [".ts", ".tsx", ".d.ts"]
+                .iter()
+                .map(|extension| with_suffix(Path::new(stem), extension))
+                .find(|source| source.is_file())
+            {
+                return Some(source);
+            }
+        }
+        if self.allow_arbitrary_extensions {
+            if let (Some(stem), Some(extension)) = (candidate.file_stem(), candidate.extension()) {
+                let mut shim = stem.to_owned();
+                shim.push(".d.");
+                shim.push(extension);
+                shim.push(".ts");
+                let shim = candidate.with_file_name(shim);
+                if shim.is_file() {
+                    return Some(shim);
+                }
+            }
+        }
+        self.extensions
+            .iter()
+            .map(|extension| with_suffix(candidate, extension))
+            .chain(
+                self.extensions
+                    .iter()
+                    .map(|extension| with_suffix(&candidate.join("index"), extension)),
+            )
+            .find(|path| path.is_file())
+    }
+
+    /// Substitute `specifier` into the best-matching `paths` pattern, trying
+    /// each substitution in order until one exists on disk.
+    fn resolve_paths_alias(&self, specifier: &str) -> Option<PathBuf> {
+        let (paths_base, paths) = self.paths.as_ref()?;
+
+        // Like tsc, prefer an exact match, then the longest matching prefix
+        let (substitutions, wildcard) = match paths.get(specifier) {
+            Some(substitutions) => (substitutions, ""),
+            None => paths
+                .iter()
+                .filter_map(|(pattern, substitutions)| {
+                    let (prefix, suffix) = pattern.split_once('*')?;
+                    let wildcard = specifier.strip_prefix(prefix)?.strip_suffix(suffix)?;
+                    Some((prefix.len(), substitutions, wildcard))
+                })
+                .max_by_key(|(prefix_length, _, _)| *prefix_length)
+                .map(|(_, substitutions, wildcard)| (substitutions, wildcard))?,
+        };
+
+        substitutions.iter().find_map(|substitution| {
+            let candidate = normalize(&paths_base.join(substitution.replace('*', wildcard)));
+            self.resolve_module_path(&candidate)
+        })
+    }
+
+    /// Resolve `specifier`, imported from a file in `importing_directory`.
+    fn resolve(
+        &self,
+        importing_directory: &Path,
+        specifier: &str,
+        resolve_path_aliases: bool,
+    ) -> Option<PathBuf> {
+        if is_relative_specifier(specifier) {
+            return self.resolve_module_path(&normalize(&importing_directory.join(specifier)));
+        }
+        if !resolve_path_aliases {
+            return None;
+        }
+        self.resolve_paths_alias(specifier).or_else(|| {
+            let base_url = self.base_url.as_ref()?;
+            self.resolve_module_path(&normalize(&base_url.join(specifier)))
+        })
+    }
+}
+
+/// Extend `included_files`, given as relative paths from the monorepo root,
+/// with the monorepo files they import, transitively. Imported files are
+/// scanned in turn, so modules reached only through chains of imports or
+/// re-exports like `export * from './a'` are found too.
+///
+/// Each file is scanned once, so import cycles terminate. Files inside
+/// `node_modules` or outside the monorepo are neither included nor scanned.
+pub(crate) fn follow_imports(
+    monorepo_root: &Path,
+    included_files: Vec<PathBuf>,
+    module_resolution: &ModuleResolution,
+    resolve_path_aliases: bool,
+) -> Vec<PathBuf> {
+    let normalized_monorepo_root = normalize(monorepo_root);
+    let mut seen: HashSet<PathBuf> = included_files.iter().cloned().collect();
+    let mut included_files = included_files;
+
+    // Imported files are appended as they are found, and scanned in turn
+    let mut next = 0;
+    while let Some(file) = included_files.get(next) {
+        next += 1;
+        let path = normalized_monorepo_root.join(file);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) => {
+                debug!(?path, error = %err, "unable to scan file for imports");
+                continue;
+            }
+        };
+        let importing_directory = path.parent().unwrap_or(&normalized_monorepo_root);
+
+        for specifier in import_specifiers(&contents) {
+            let Some(imported_file) = module_resolution
+                .resolve(importing_directory, &specifier, resolve_path_aliases)
+                .and_then(|resolved| {
+                    resolved
+                        .strip_prefix(&normalized_monorepo_root)
+                        .ok()
+                        .map(ToOwned::to_owned)
+                })
+            else {
+                continue;
+            };
+            if is_child_of_node_modules(&imported_file) {
+                continue;
+            }
+            if seen.insert(imported_file.clone()) {
+                included_files.push(imported_file);
+            }
+        }
+    }
+
+    included_files
+}
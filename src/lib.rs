@@ -39,14 +39,39 @@
 //! You might want to use the estimation method if speed is a concern, because it
 //! is several orders of magnitude faster than the exact method.
 //!
+//! Enumeration depends only on the monorepo on disk: the monorepo manifest,
+//! package manifests, tsconfig files and the files they include, and in exact
+//! mode the output of the TypeScript compiler. The working directory is only
+//! used to resolve a relative `monorepo_root`, and tsc runs in the monorepo
+//! root with an environment limited to `PATH` unless
+//! [`EnumerationOptions::inherit_compiler_env`] is set.
+//!
+//! Diagnostics are emitted through [tracing], with one span per enumerated
+//! package carrying the package name, tsconfig path, and calculation method.
+//! When no tracing subscriber is installed, events are forwarded to the [log]
+//! crate instead.
+//!
 //! [listfilesonly]: https://www.typescriptlang.org/docs/handbook/compiler-options.html#compiler-options
 //! [tsconfig exclude]: https://www.typescriptlang.org/tsconfig#exclude
+//! [`EnumerationOptions::inherit_compiler_env`]: options::EnumerationOptions::inherit_compiler_env
+//! [tracing]: https://docs.rs/tracing
+//! [log]: https://docs.rs/log
 
 #![forbid(unsafe_code)]
 #![deny(missing_debug_implementations)]
 
+mod build_info;
+mod digest;
+pub mod discover;
 pub mod estimate;
 pub mod exact;
+mod extends;
+mod imports;
 pub mod io;
+pub mod options;
 pub mod path;
+mod reference_directive;
+pub mod report;
+pub mod result;
 pub mod typescript_package;
+mod walk;
@@ -0,0 +1,1368 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    env,
+    ffi::OsString,
+    fmt::Display,
+    hash::Hash,
+    io,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::Arc,
+};
+
+use rayon::prelude::*;
+
+use crate::{
+    digest::package_digest,
+    estimate::{self, is_empty_project},
+    exact,
+    path::{normalize, relative_path},
+    result::{DependencyTree, EnumerationResult, PartitionedFiles, Warning},
+    typescript_package::{
+        group_by_package_name, DependencyGraph, Enumeration, TypescriptConfigFile,
+        TypescriptPackage,
+    },
+};
+
+/// Environment variables passed through to the compiler unless the whole
+/// environment is inherited.
+const PRESERVED_COMPILER_ENV: [&str; 3] = ["PATH", "PATHEXT", "SYSTEMROOT"];
+
+/// Method used to calculate the list of files included in a TypeScript
+/// compilation. See the [crate-level documentation](crate) for the tradeoffs
+/// between the two.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Calculation {
+    /// Use the `include` globs from each package's tsconfig.json.
+    Estimate,
+    /// Use the TypeScript compiler's `--listFilesOnly` flag.
+    #[default]
+    Exact,
+    /// Run both the estimate and the exact calculation, returning the exact
+    /// result but failing if the two disagree on any path not listed in
+    /// [`EnumerationOptions::known_divergent_paths`].
+    ///
+    /// This is as expensive as both methods combined, and is intended as a
+    /// guard rail in CI against the estimate silently drifting from tsc.
+    Verified,
+}
+
+/// How far through each requested package's internal dependencies
+/// enumeration reaches.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DependencyDepth {
+    /// Enumerate the requested package and its immediate internal
+    /// dependencies only.
+    Direct,
+    /// Enumerate the requested package and every internal dependency it
+    /// reaches, however indirectly.
+    #[default]
+    Transitive,
+}
+
+/// Inputs to the per-package digest reported by
+/// [`EnumerationOptions::enumerate_detailed`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Digest {
+    /// Hash the sorted paths of each package's files. Cheap, but blind to
+    /// edits that leave the set of files unchanged.
+    Paths,
+    /// Hash the sorted paths along with the contents of each package's files.
+    /// Reads every included file.
+    Contents,
+}
+
+/// Base against which enumerated file paths are expressed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PathStyle {
+    /// Relative paths from the monorepo root, like `packages/foo/src/index.ts`.
+    #[default]
+    RelativeToMonorepoRoot,
+    /// Relative paths from the directory of the package that includes the
+    /// file, like `src/index.ts`. Files belonging to an internal dependency
+    /// are expressed relative to that dependency's own package directory.
+    RelativeToPackage,
+}
+
+impl PathStyle {
+    /// Re-base `files`, given as relative paths from the monorepo root, into
+    /// this path style.
+    pub(crate) fn rebase(
+        &self,
+        typescript_package: &TypescriptPackage,
+        files: Vec<PathBuf>,
+    ) -> Vec<PathBuf> {
+        match self {
+            PathStyle::RelativeToMonorepoRoot => files,
+            PathStyle::RelativeToPackage => files
+                .into_iter()
+                .map(|file| self.rebase_file(&typescript_package.package_directory, file))
+                .collect(),
+        }
+    }
+
+    /// Re-base `file`, given as a relative path from the monorepo root, into
+    /// this path style for the package in `package_directory`.
+    fn rebase_file(&self, package_directory: &Path, file: PathBuf) -> PathBuf {
+        match self {
+            PathStyle::RelativeToMonorepoRoot => file,
+            PathStyle::RelativeToPackage => match file.strip_prefix(package_directory) {
+                Ok(relative_path) => relative_path.to_owned(),
+                // The file lives outside the package directory
+                Err(_) => relative_path(package_directory, &file),
+            },
+        }
+    }
+}
+
+type PathTransformFn = dyn Fn(&Path) -> Option<PathBuf> + Send + Sync;
+
+/// Caller-supplied policy rewriting or dropping each emitted file.
+#[derive(Clone)]
+pub(crate) struct PathTransform(Arc<PathTransformFn>);
+
+impl std::fmt::Debug for PathTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("PathTransform").finish_non_exhaustive()
+    }
+}
+
+/// Files to add to or remove from a package's enumerated files, expressed in
+/// the configured [`PathStyle`], for packages whose enumeration is known to
+/// be wrong. See [`EnumerationOptions::package_overrides`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PackageOverride {
+    pub(crate) add: BTreeSet<PathBuf>,
+    pub(crate) remove: BTreeSet<PathBuf>,
+}
+
+impl PackageOverride {
+    /// Report `files` as included by the package, whether or not the
+    /// enumeration found them.
+    pub fn add_files<I>(mut self, files: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<PathBuf>,
+    {
+        self.add.extend(files.into_iter().map(Into::into));
+        self
+    }
+
+    /// Drop `files` from the package's enumerated files.
+    pub fn remove_files<I>(mut self, files: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<PathBuf>,
+    {
+        self.remove.extend(files.into_iter().map(Into::into));
+        self
+    }
+}
+
+type PackageDirectoryFn = dyn Fn(&Path) -> PathBuf + Send + Sync;
+
+/// Caller-supplied mapping from a tsconfig file to its package directory.
+#[derive(Clone)]
+pub(crate) struct PackageDirectoryResolver(Arc<PackageDirectoryFn>);
+
+impl std::fmt::Debug for PackageDirectoryResolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("PackageDirectoryResolver")
+            .finish_non_exhaustive()
+    }
+}
+
+/// Knobs controlling how tsconfig includes are enumerated.
+///
+/// Every option has a sensible default, so callers only need to set the
+/// options they care about:
+///
+/// ```no_run
+/// use tsconfig_includes::options::{Calculation, EnumerationOptions};
+///
+/// let included_files = EnumerationOptions::default()
+///     .calculation(Calculation::Estimate)
+///     .enumerate("path/to/monorepo", ["packages/foo/tsconfig.json"])?;
+/// # Ok::<(), tsconfig_includes::options::Error>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct EnumerationOptions {
+    pub(crate) calculation: Calculation,
+    pub(crate) use_build_info: bool,
+    pub(crate) known_divergent_paths: HashSet<PathBuf>,
+    pub(crate) path_style: PathStyle,
+    pub(crate) preserve_compile_order: bool,
+    pub(crate) follow_reference_directives: bool,
+    pub(crate) skip_declaration_only_packages: bool,
+    pub(crate) follow_imports: bool,
+    pub(crate) resolve_path_aliases: bool,
+    pub(crate) best_effort: bool,
+    pub(crate) check_files_exist: bool,
+    pub(crate) compiler_env: Vec<(OsString, OsString)>,
+    pub(crate) inherit_compiler_env: bool,
+    pub(crate) dependency_depth: DependencyDepth,
+    pub(crate) compiler: Option<PathBuf>,
+    pub(crate) digest: Option<Digest>,
+    pub(crate) path_transform: Option<PathTransform>,
+    pub(crate) package_directory_resolver: Option<PackageDirectoryResolver>,
+    pub(crate) package_overrides: HashMap<String, PackageOverride>,
+    pub(crate) include_path_mapped_declarations: bool,
+    pub(crate) max_depth: Option<usize>,
+    pub(crate) include_bases: HashMap<PathBuf, PathBuf>,
+    pub(crate) allow_missing_internal_dependencies: bool,
+}
+
+impl EnumerationOptions {
+    /// Choose the method used to calculate included files.
+    pub fn calculation(mut self, calculation: Calculation) -> Self {
+        self.calculation = calculation;
+        self
+    }
+
+    /// In exact mode, read each package's file list from its `.tsbuildinfo`
+    /// when it is fresh instead of invoking the TypeScript compiler.
+    ///
+    /// See [`exact::tsconfig_includes_by_package_name_incremental`].
+    pub fn use_build_info(mut self, use_build_info: bool) -> Self {
+        self.use_build_info = use_build_info;
+        self
+    }
+
+    /// Paths, expressed in the configured [`PathStyle`], that
+    /// [`Calculation::Verified`] tolerates appearing in only one of the
+    /// estimate and exact results.
+    pub fn known_divergent_paths<I>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<PathBuf>,
+    {
+        self.known_divergent_paths = paths.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Correct the enumerated files of the packages named by scoped package
+    /// name in `overrides`, once enumeration is complete: removed files are
+    /// dropped, added files are included, and the result is re-sorted unless
+    /// compile order is preserved, in which case added files come last.
+    ///
+    /// Overrides apply whatever the configured [`Calculation`], after
+    /// [`Calculation::Verified`] compares the estimate and exact results, and
+    /// only to packages that were enumerated. Removing a file the package did
+    /// not include is reported with a [`Warning::UnmatchedOverride`] by
+    /// [`Self::enumerate_detailed`]. Results grouped by tsconfig, as from
+    /// [`Self::enumerate_by_tsconfig`], are not overridden.
+    pub fn package_overrides<I, K>(mut self, overrides: I) -> Self
+    where
+        I: IntoIterator<Item = (K, PackageOverride)>,
+        K: Into<String>,
+    {
+        self.package_overrides = overrides
+            .into_iter()
+            .map(|(package_name, package_override)| (package_name.into(), package_override))
+            .collect();
+        self
+    }
+
+    /// Apply the configured [`PackageOverride`]s to `included_files`, grouped
+    /// by scoped package name, returning a warning for each removed file
+    /// that was not included.
+    fn apply_overrides(
+        &self,
+        included_files: &mut HashMap<String, Vec<PathBuf>>,
+        preserve_compile_order: bool,
+    ) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+        for (package_name, package_override) in &self.package_overrides {
+            let Some(files) = included_files.get_mut(package_name) else {
+                continue;
+            };
+            let mut removed = HashSet::new();
+            files.retain(|file| {
+                let remove = package_override.remove.contains(file);
+                if remove {
+                    removed.insert(file.clone());
+                }
+                !remove
+            });
+            warnings.extend(
+                package_override
+                    .remove
+                    .iter()
+                    .filter(|file| !removed.contains(*file))
+                    .map(|file| Warning::UnmatchedOverride {
+                        package: package_name.clone(),
+                        file: file.clone(),
+                    }),
+            );
+            let mut seen: HashSet<PathBuf> = files.iter().cloned().collect();
+            files.extend(
+                package_override
+                    .add
+                    .iter()
+                    .filter(|file| seen.insert((*file).clone()))
+                    .cloned(),
+            );
+            if !preserve_compile_order {
+                files.sort_unstable();
+            }
+        }
+        warnings
+    }
+
+    /// Choose the base against which enumerated file paths are expressed.
+    pub fn path_style(mut self, path_style: PathStyle) -> Self {
+        self.path_style = path_style;
+        self
+    }
+
+    /// Choose how far through each requested package's internal
+    /// dependencies to enumerate.
+    pub fn dependency_depth(mut self, dependency_depth: DependencyDepth) -> Self {
+        self.dependency_depth = dependency_depth;
+        self
+    }
+
+    /// In exact mode, return each package's files in the order tsc reports
+    /// them instead of alphabetically. Useful for seeing entry points first.
+    ///
+    /// The estimate has no meaningful compile order, so this option has no
+    /// effect on [`Calculation::Estimate`].
+    pub fn preserve_compile_order(mut self, preserve_compile_order: bool) -> Self {
+        self.preserve_compile_order = preserve_compile_order;
+        self
+    }
+
+    /// In estimate mode, also include files named by the triple-slash
+    /// `/// <reference path="..." />` and `/// <reference types="..." />`
+    /// directives at the top of each included file, transitively.
+    ///
+    /// The exact calculation always follows these directives, so this option
+    /// has no effect on [`Calculation::Exact`].
+    pub fn follow_reference_directives(mut self, follow_reference_directives: bool) -> Self {
+        self.follow_reference_directives = follow_reference_directives;
+        self
+    }
+
+    /// In estimate mode, also include the monorepo files imported by each
+    /// included file through relative specifiers, like `./lib`, transitively.
+    /// Imported files are scanned for imports in turn, so the modules behind
+    /// chains of barrel files re-exporting `export * from './a'` are found
+    /// too. Files in `node_modules` are never followed.
+    ///
+    /// The exact calculation always follows imports, so this option has no
+    /// effect on [`Calculation::Exact`].
+    pub fn follow_imports(mut self, follow_imports: bool) -> Self {
+        self.follow_imports = follow_imports;
+        self
+    }
+
+    /// In estimate mode, also include the `.d.ts` files that
+    /// `compilerOptions.paths` substitutions name directly, like
+    /// `"globals": ["./types/globals.d.ts"]`, even when no include glob
+    /// matches them. Substitutions resolve against `compilerOptions.baseUrl`,
+    /// or the directory of the tsconfig declaring `paths`.
+    ///
+    /// This is cheaper than [`Self::follow_imports`], and independent of it.
+    /// The exact calculation includes these files whenever they are used, so
+    /// this option has no effect on [`Calculation::Exact`].
+    pub fn include_path_mapped_declarations(
+        mut self,
+        include_path_mapped_declarations: bool,
+    ) -> Self {
+        self.include_path_mapped_declarations = include_path_mapped_declarations;
+        self
+    }
+
+    /// In estimate mode, descend at most `max_depth` directories below each
+    /// package directory while matching include globs, where the files
+    /// directly inside the package directory are at depth 1. Bounds the walk
+    /// for packages whose globs only reach shallow files, but that contain
+    /// deeply-nested directories, like generated code. Files beyond the limit
+    /// are missed even when a glob matches them.
+    ///
+    /// The walk is unbounded by default. The exact calculation does not walk
+    /// directories, so this option has no effect on [`Calculation::Exact`].
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// In estimate mode, resolve the `include` and `files` entries of
+    /// `tsconfig` against `include_base` instead of the tsconfig's own
+    /// directory, and walk `include_base` for matching files. Both are
+    /// relative paths from the monorepo root. Useful for tsconfigs generated
+    /// into a directory apart from the sources they describe.
+    ///
+    /// Entries inherited through `extends` from a tsconfig in another
+    /// directory still resolve against that directory. Call once per tsconfig to
+    /// override; other tsconfigs resolve their globs as tsc does. The exact
+    /// calculation always resolves globs the way tsc does, so this option has
+    /// no effect on [`Calculation::Exact`].
+    pub fn include_base<T, B>(mut self, tsconfig: T, include_base: B) -> Self
+    where
+        T: AsRef<Path>,
+        B: Into<PathBuf>,
+    {
+        self.include_bases
+            .insert(normalize(tsconfig.as_ref()), include_base.into());
+        self
+    }
+
+    /// Report an internal dependency declared through the workspace protocol,
+    /// like `"workspace:^"`, that names no package in the monorepo as a
+    /// [`Warning::MissingInternalDependency`] instead of failing enumeration.
+    ///
+    /// Such dependencies usually signal a broken workspace link, and would
+    /// otherwise leave the dependency's files silently missing from the
+    /// results.
+    pub fn allow_missing_internal_dependencies(
+        mut self,
+        allow_missing_internal_dependencies: bool,
+    ) -> Self {
+        self.allow_missing_internal_dependencies = allow_missing_internal_dependencies;
+        self
+    }
+
+    /// The directory `tsconfig_file`'s globs resolve against, when overridden.
+    pub(crate) fn include_base_of(&self, tsconfig_file: &TypescriptConfigFile) -> Option<&Path> {
+        self.include_bases
+            .get(&normalize(tsconfig_file.as_path()))
+            .map(PathBuf::as_path)
+    }
+
+    /// When following imports, also resolve non-relative specifiers through
+    /// the tsconfig's `compilerOptions.paths` aliases and
+    /// `compilerOptions.baseUrl`.
+    pub fn resolve_path_aliases(mut self, resolve_path_aliases: bool) -> Self {
+        self.resolve_path_aliases = resolve_path_aliases;
+        self
+    }
+
+    /// In exact mode, skip spawning tsc for packages whose include globs match
+    /// only hand-written `.d.ts` files, returning those files directly.
+    ///
+    /// LIMITATION: files the declarations pull in through `import` or
+    /// `/// <reference` are not reported for skipped packages.
+    pub fn skip_declaration_only_packages(mut self, skip_declaration_only_packages: bool) -> Self {
+        self.skip_declaration_only_packages = skip_declaration_only_packages;
+        self
+    }
+
+    /// In exact mode, keep the files tsc lists even when it exits with a
+    /// non-zero status, as it does for packages that fail to typecheck,
+    /// instead of failing enumeration. Each such package is reported with a
+    /// [`Warning::CompilerDiagnostics`] carrying tsc's stderr by
+    /// [`Self::enumerate_detailed`].
+    pub fn best_effort(mut self, best_effort: bool) -> Self {
+        self.best_effort = best_effort;
+        self
+    }
+
+    /// In exact mode, check that each file tsc or a `.tsbuildinfo` reports
+    /// exists on disk, dropping those that do not, as can happen on a racing
+    /// filesystem or with stale build info. Each dropped file is reported
+    /// with a [`Warning::MissingEnumeratedFile`] by
+    /// [`Self::enumerate_detailed`].
+    ///
+    /// Off by default, since it costs a stat per file. The estimate only
+    /// reports files it walked, so this option has no effect on
+    /// [`Calculation::Estimate`].
+    pub fn check_files_exist(mut self, check_files_exist: bool) -> Self {
+        self.check_files_exist = check_files_exist;
+        self
+    }
+
+    /// In exact mode, invoke this program instead of `tsc`. It must accept
+    /// tsc's `--listFilesOnly --project <tsconfig>` arguments, as drop-in
+    /// wrappers like `vue-tsc` do. Files it lists beyond TypeScript sources,
+    /// like `.vue` components, are reported like any other.
+    ///
+    /// The program is looked up on `PATH` unless given as a path, and a
+    /// relative path is resolved against the monorepo root.
+    ///
+    /// A compiler too old to know `--listFilesOnly` is asked for
+    /// `--listFiles --noEmit` instead, which also type-checks each package.
+    /// Type errors in a package that still lists its files are reported as
+    /// [`Warning::CompilerDiagnostics`] rather than failing enumeration,
+    /// which fails only when the compiler lists no files at all, unless
+    /// [`Self::best_effort`] is set.
+    pub fn compiler<C: Into<PathBuf>>(mut self, compiler: C) -> Self {
+        self.compiler = Some(compiler.into());
+        self
+    }
+
+    /// The program spawned in exact mode.
+    pub(crate) fn compiler_program(&self) -> &Path {
+        self.compiler.as_deref().unwrap_or_else(|| Path::new("tsc"))
+    }
+
+    /// In exact mode, pass this process's whole environment through to each
+    /// spawned tsc process.
+    ///
+    /// By default tsc only sees `PATH`, which is needed to find tsc and
+    /// node, `PATHEXT` and `SYSTEMROOT` on Windows, and any variables set
+    /// with [`compiler_env`](Self::compiler_env), so variables like
+    /// `NODE_OPTIONS` cannot change the enumerated files behind the caller's
+    /// back.
+    pub fn inherit_compiler_env(mut self, inherit_compiler_env: bool) -> Self {
+        self.inherit_compiler_env = inherit_compiler_env;
+        self
+    }
+
+    /// The command spawning the compiler for a monorepo, without arguments.
+    ///
+    /// The compiler runs in `monorepo_root`, whatever this process's working
+    /// directory, and a relative compiler path is resolved against it.
+    pub(crate) fn compiler_command(&self, monorepo_root: &Path) -> Command {
+        let program = self.compiler_program();
+        // A bare program name is looked up on PATH instead
+        let program = if program.is_relative() && program.components().count() > 1 {
+            monorepo_root.join(program)
+        } else {
+            program.to_owned()
+        };
+        let mut command = Command::new(program);
+        command.current_dir(monorepo_root);
+        if !self.inherit_compiler_env {
+            command.env_clear();
+            for key in PRESERVED_COMPILER_ENV {
+                if let Some(value) = env::var_os(key) {
+                    command.env(key, value);
+                }
+            }
+        }
+        command.envs(self.compiler_env.iter().map(|(key, value)| (key, value)));
+        command
+    }
+
+    /// In exact mode, set these environment variables on each spawned tsc
+    /// process. Useful for compiler plugins, like those installed through
+    /// ts-patch, that read their configuration from the environment.
+    ///
+    /// These are set on top of the variables passed through by default, see
+    /// [`inherit_compiler_env`](Self::inherit_compiler_env).
+    pub fn compiler_env<I, K, V>(mut self, vars: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<OsString>,
+        V: Into<OsString>,
+    {
+        self.compiler_env = vars
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .collect();
+        self
+    }
+
+    /// Pass each enumerated file, expressed in the configured [`PathStyle`],
+    /// through `transform`, keeping the path it returns or dropping the file
+    /// when it returns `None`. Useful for dropping generated files or
+    /// rewriting paths into a canonical form.
+    ///
+    /// The transform runs before each package's files are sorted and
+    /// deduplicated, so results remain ordered even when it maps several
+    /// files to the same path.
+    pub fn transform_paths<F>(mut self, transform: F) -> Self
+    where
+        F: Fn(&Path) -> Option<PathBuf> + Send + Sync + 'static,
+    {
+        self.path_transform = Some(PathTransform(Arc::new(transform)));
+        self
+    }
+
+    /// Locate the package owning each requested tsconfig file with `resolve`,
+    /// which maps the tsconfig's relative path from the monorepo root to the
+    /// package directory's, instead of assuming the tsconfig sits beside the
+    /// package's `package.json`. Useful for layouts that generate build
+    /// configurations into a tree mirroring the packages.
+    ///
+    /// The package directory's `package.json` names the package and declares
+    /// its internal dependencies, and [`PathStyle::RelativeToPackage`] paths
+    /// are relative to it. Include globs still resolve against the tsconfig's
+    /// own directory, as they do in tsc, unless overridden with
+    /// [`Self::include_base`], and internal dependencies are still
+    /// enumerated through the `tsconfig.json` beside their `package.json`.
+    /// Enumeration fails when `resolve` returns a directory holding no
+    /// package listed in the monorepo manifest.
+    pub fn resolve_package_directory<F>(mut self, resolve: F) -> Self
+    where
+        F: Fn(&Path) -> PathBuf + Send + Sync + 'static,
+    {
+        self.package_directory_resolver = Some(PackageDirectoryResolver(Arc::new(resolve)));
+        self
+    }
+
+    /// The directory of the package owning `tsconfig_file`, both as relative
+    /// paths from the monorepo root.
+    pub(crate) fn package_directory_of(&self, tsconfig_file: &Path) -> PathBuf {
+        let tsconfig_file = normalize(tsconfig_file);
+        match &self.package_directory_resolver {
+            Some(PackageDirectoryResolver(resolve)) => normalize(&resolve(&tsconfig_file)),
+            None => tsconfig_file
+                .parent()
+                .map(ToOwned::to_owned)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Re-base `files`, given as relative paths from the monorepo root, into
+    /// the configured [`PathStyle`] and apply the configured path transform.
+    pub(crate) fn emitted_files(
+        &self,
+        typescript_package: &TypescriptPackage,
+        files: Vec<PathBuf>,
+    ) -> Vec<PathBuf> {
+        let files = self.path_style.rebase(typescript_package, files);
+        match &self.path_transform {
+            Some(PathTransform(transform)) => {
+                let mut seen = HashSet::new();
+                files
+                    .iter()
+                    .filter_map(|file| transform(file))
+                    // Keep the first occurrence to preserve compile order
+                    .filter(|file| seen.insert(file.clone()))
+                    .collect()
+            }
+            None => files,
+        }
+    }
+
+    /// Whether the configured path transform keeps `file`, a relative path
+    /// from the monorepo root included through `tsconfig_file`.
+    fn keeps_file(&self, tsconfig_file: &Path, file: &Path) -> bool {
+        match &self.path_transform {
+            Some(PathTransform(transform)) => {
+                let package_directory = self.package_directory_of(tsconfig_file);
+                transform(
+                    &self
+                        .path_style
+                        .rebase_file(&package_directory, normalize(file)),
+                )
+                .is_some()
+            }
+            None => true,
+        }
+    }
+
+    /// Also compute a SHA-256 digest of each package's files, reported in
+    /// [`EnumerationResult::digests`] by [`Self::enumerate_detailed`]. The
+    /// digest changes whenever the package's set of input files does, making
+    /// it suitable as a build cache key.
+    ///
+    /// Digests cover the paths as expressed in the configured [`PathStyle`].
+    pub fn digest(mut self, digest: Digest) -> Self {
+        self.digest = Some(digest);
+        self
+    }
+
+    /// Enumerate source code files used by the TypeScript compiler during
+    /// compilation. The return value is a list of alphabetically-sorted
+    /// relative paths (see [`PathStyle`]), grouped by scoped package name.
+    ///
+    /// - `monorepo_root` may be an absolute path
+    /// - `tsconfig_files` should be relative paths from the monorepo root
+    pub fn enumerate<P, T>(
+        &self,
+        monorepo_root: P,
+        tsconfig_files: T,
+    ) -> Result<HashMap<String, Vec<PathBuf>>, Error>
+    where
+        P: AsRef<Path> + Sync,
+        T: IntoIterator,
+        T::Item: AsRef<Path>,
+    {
+        let included_files = match self.calculation {
+            Calculation::Estimate => estimate::tsconfig_includes_by_package_name_with_options(
+                monorepo_root,
+                tsconfig_files,
+                self,
+            )?,
+            Calculation::Exact => exact::tsconfig_includes_by_package_name_with_options(
+                monorepo_root,
+                tsconfig_files,
+                self,
+            )?,
+            Calculation::Verified => {
+                let monorepo_root = monorepo_root.as_ref();
+                let tsconfig_files: Vec<PathBuf> = tsconfig_files
+                    .into_iter()
+                    .map(|tsconfig_file| tsconfig_file.as_ref().to_owned())
+                    .collect();
+                let estimated = estimate::tsconfig_includes_by_package_name_with_options(
+                    monorepo_root,
+                    &tsconfig_files,
+                    self,
+                )?;
+                let exact = exact::tsconfig_includes_by_package_name_with_options(
+                    monorepo_root,
+                    &tsconfig_files,
+                    self,
+                )?;
+                self.verify(estimated, exact, |package_name| package_name.clone())?
+            }
+        };
+        let mut included_files = included_files;
+        self.apply_overrides(&mut included_files, self.preserves_compile_order());
+        Ok(included_files)
+    }
+
+    /// Enumerate source code files used by the TypeScript compiler during
+    /// compilation. The return value is a list of alphabetically-sorted
+    /// relative paths (see [`PathStyle`]), grouped by the relative path from
+    /// the monorepo root to the tsconfig file that includes them.
+    ///
+    /// - `monorepo_root` may be an absolute path
+    /// - `tsconfig_files` should be relative paths from the monorepo root
+    pub fn enumerate_by_tsconfig<P, T>(
+        &self,
+        monorepo_root: P,
+        tsconfig_files: T,
+    ) -> Result<HashMap<PathBuf, Vec<PathBuf>>, Error>
+    where
+        P: AsRef<Path> + Sync,
+        T: IntoIterator,
+        T::Item: AsRef<Path>,
+    {
+        let included_files = match self.calculation {
+            Calculation::Estimate => estimate::tsconfig_includes_by_tsconfig_with_options(
+                monorepo_root,
+                tsconfig_files,
+                self,
+            )?,
+            Calculation::Exact => exact::tsconfig_includes_by_tsconfig_with_options(
+                monorepo_root,
+                tsconfig_files,
+                self,
+            )?,
+            Calculation::Verified => {
+                let monorepo_root = monorepo_root.as_ref();
+                let tsconfig_files: Vec<PathBuf> = tsconfig_files
+                    .into_iter()
+                    .map(|tsconfig_file| tsconfig_file.as_ref().to_owned())
+                    .collect();
+                let estimated = estimate::tsconfig_includes_by_tsconfig_with_options(
+                    monorepo_root,
+                    &tsconfig_files,
+                    self,
+                )?;
+                let exact = exact::tsconfig_includes_by_tsconfig_with_options(
+                    monorepo_root,
+                    &tsconfig_files,
+                    self,
+                )?;
+                self.verify(estimated, exact, |tsconfig| {
+                    tsconfig.to_string_lossy().into_owned()
+                })?
+            }
+        };
+        Ok(included_files)
+    }
+
+    /// Enumerate every package in the composite build graph rooted at
+    /// `solution_tsconfig`, following its project references the way
+    /// `tsc --build` does. The return value is a list of alphabetically-sorted
+    /// relative paths (see [`PathStyle`]), grouped by scoped package name.
+    ///
+    /// This always uses the exact calculation, whatever the configured
+    /// [`Calculation`]. See [`exact::tsconfig_includes_by_package_name_build`]
+    /// for how files are attributed to packages.
+    ///
+    /// - `monorepo_root` may be an absolute path
+    /// - `solution_tsconfig` should be a relative path from the monorepo root
+    pub fn enumerate_build<P, Q>(
+        &self,
+        monorepo_root: P,
+        solution_tsconfig: Q,
+    ) -> Result<HashMap<String, Vec<PathBuf>>, Error>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        let mut included_files = exact::tsconfig_includes_by_package_name_build_with_options(
+            monorepo_root,
+            solution_tsconfig,
+            self,
+        )?;
+        self.apply_overrides(&mut included_files, self.preserve_compile_order);
+        Ok(included_files)
+    }
+
+    /// Enumerate like [`Self::enumerate`], but without letting one broken
+    /// package abort the whole run. The return value pairs the files of every
+    /// package that was enumerated successfully with the error encountered
+    /// by every package that was not, sorted by scoped package name.
+    ///
+    /// This always uses the estimate calculation, whatever the configured
+    /// [`Calculation`]. See [`estimate::tsconfig_includes_by_package_name_partial`]
+    /// for which errors still abort the run.
+    ///
+    /// - `monorepo_root` may be an absolute path
+    /// - `tsconfig_files` should be relative paths from the monorepo root
+    #[allow(clippy::type_complexity)]
+    pub fn enumerate_partial<P, T>(
+        &self,
+        monorepo_root: P,
+        tsconfig_files: T,
+    ) -> Result<
+        (
+            HashMap<String, Vec<PathBuf>>,
+            Vec<(String, estimate::Error)>,
+        ),
+        Error,
+    >
+    where
+        P: AsRef<Path> + Sync,
+        T: IntoIterator,
+        T::Item: AsRef<Path>,
+    {
+        let (mut included_files, errors) =
+            estimate::tsconfig_includes_by_package_name_partial_with_options(
+                monorepo_root,
+                tsconfig_files,
+                self,
+            )?;
+        self.apply_overrides(&mut included_files, false);
+        Ok((included_files, errors))
+    }
+
+    /// Like [`Self::enumerate`], but also report the tsconfig files each
+    /// package was enumerated through, and [`Warning`]s about the enumerated
+    /// packages, such as packages whose tsconfig enumerates nothing at all.
+    ///
+    /// Internal dependencies without a tsconfig.json are skipped by every
+    /// enumeration, and reported here.
+    pub fn enumerate_detailed<P, T>(
+        &self,
+        monorepo_root: P,
+        tsconfig_files: T,
+    ) -> Result<EnumerationResult, Error>
+    where
+        P: AsRef<Path> + Sync,
+        T: IntoIterator,
+        T::Item: AsRef<Path>,
+    {
+        let monorepo_root = monorepo_root.as_ref();
+        let enumeration = self.enumeration(monorepo_root, tsconfig_files)?;
+        self.detailed(monorepo_root, enumeration, self.preserves_compile_order())
+    }
+
+    /// Like [`Self::enumerate_build`], but also report the tsconfig files
+    /// each package was attributed to, and [`Warning`]s about the enumerated
+    /// packages, such as listed files found missing when checking that files
+    /// exist.
+    pub fn enumerate_build_detailed<P, Q>(
+        &self,
+        monorepo_root: P,
+        solution_tsconfig: Q,
+    ) -> Result<EnumerationResult, Error>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        let monorepo_root = monorepo_root.as_ref();
+        let enumeration = exact::enumerate_build(monorepo_root, solution_tsconfig, self)?;
+        self.detailed(monorepo_root, enumeration, self.preserve_compile_order)
+    }
+
+    /// Group an enumeration by scoped package name, warning about empty
+    /// projects and unmatched overrides and computing digests on the way.
+    fn detailed(
+        &self,
+        monorepo_root: &Path,
+        enumeration: Enumeration,
+        preserve_compile_order: bool,
+    ) -> Result<EnumerationResult, Error> {
+        let packages = enumeration.included_files;
+        let mut warnings = enumeration.warnings;
+
+        warnings.extend(
+            packages
+                .iter()
+                .filter(|(typescript_package, included_files)| {
+                    included_files.is_empty()
+                        && is_empty_project(monorepo_root, &typescript_package.tsconfig_file)
+                })
+                .map(|(typescript_package, _)| Warning::EmptyProject {
+                    package: typescript_package.scoped_package_name.clone(),
+                }),
+        );
+
+        let mut tsconfig_files: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        let mut package_directories: HashMap<String, PathBuf> = HashMap::new();
+        for (typescript_package, _) in &packages {
+            package_directories
+                .entry(typescript_package.scoped_package_name.clone())
+                .or_insert_with(|| typescript_package.package_directory.clone());
+            tsconfig_files
+                .entry(typescript_package.scoped_package_name.clone())
+                .or_default()
+                .push(typescript_package.tsconfig_file.as_path().to_owned());
+        }
+        for tsconfig_files in tsconfig_files.values_mut() {
+            tsconfig_files.sort_unstable();
+        }
+
+        let mut included_files = group_by_package_name(packages, preserve_compile_order);
+        warnings.extend(self.apply_overrides(&mut included_files, preserve_compile_order));
+        warnings.sort_unstable();
+        warnings.dedup();
+        let digests = match self.digest {
+            Some(digest) => included_files
+                .par_iter()
+                .map(|(package_name, files)| {
+                    let package_directory = &package_directories[package_name];
+                    let resolve = |file: &Path| match self.path_style {
+                        PathStyle::RelativeToMonorepoRoot => monorepo_root.join(file),
+                        PathStyle::RelativeToPackage => {
+                            monorepo_root.join(package_directory).join(file)
+                        }
+                    };
+                    let digest =
+                        package_digest(files, digest, resolve).map_err(|(path, err)| Error {
+                            kind: ErrorKind::Digest(path, err),
+                        })?;
+                    Ok((package_name.clone(), digest))
+                })
+                .collect::<Result<_, Error>>()?,
+            None => HashMap::new(),
+        };
+
+        Ok(EnumerationResult {
+            included_files,
+            tsconfig_files,
+            digests,
+            warnings,
+            packages_without_tsconfig: enumeration.packages_without_tsconfig.into_iter().collect(),
+        })
+    }
+
+    /// Enumerate like [`Self::enumerate`], but nest each requested package's
+    /// internal dependencies beneath it instead of flattening them. The
+    /// return value maps the scoped name of each requested package to the
+    /// tree of packages it pulled in, each carrying its own
+    /// alphabetically-sorted relative paths (see [`PathStyle`]).
+    ///
+    /// A package reached along several paths appears in each subtree, and
+    /// an internal dependency without a tsconfig.json appears with no files.
+    /// Dependency cycles are cut where a package would reappear beneath
+    /// itself.
+    ///
+    /// - `monorepo_root` may be an absolute path
+    /// - `tsconfig_files` should be relative paths from the monorepo root
+    pub fn enumerate_tree<P, T>(
+        &self,
+        monorepo_root: P,
+        tsconfig_files: T,
+    ) -> Result<HashMap<String, DependencyTree>, Error>
+    where
+        P: AsRef<Path> + Sync,
+        T: IntoIterator,
+        T::Item: AsRef<Path>,
+    {
+        let enumeration = self.enumeration(monorepo_root.as_ref(), tsconfig_files)?;
+        let mut included_files =
+            group_by_package_name(enumeration.included_files, self.preserves_compile_order());
+        self.apply_overrides(&mut included_files, self.preserves_compile_order());
+        let dependency_graph = enumeration.dependency_graph;
+        Ok(dependency_graph
+            .requested_packages
+            .iter()
+            .map(|package_name| {
+                let tree = dependency_tree(
+                    package_name,
+                    &included_files,
+                    &dependency_graph,
+                    &mut Vec::new(),
+                );
+                (package_name.clone(), tree)
+            })
+            .collect())
+    }
+
+    /// Enumerate like [`Self::enumerate`], but partition each package's files
+    /// into [`PartitionedFiles`] buckets by their
+    /// [`FileKind`](crate::result::FileKind), so callers treating source,
+    /// declaration, JavaScript and JSON files differently need not classify
+    /// them again.
+    ///
+    /// - `monorepo_root` may be an absolute path
+    /// - `tsconfig_files` should be relative paths from the monorepo root
+    pub fn enumerate_partitioned<P, T>(
+        &self,
+        monorepo_root: P,
+        tsconfig_files: T,
+    ) -> Result<HashMap<String, PartitionedFiles>, Error>
+    where
+        P: AsRef<Path> + Sync,
+        T: IntoIterator,
+        T::Item: AsRef<Path>,
+    {
+        let included_files = self.enumerate(monorepo_root, tsconfig_files)?;
+        Ok(included_files
+            .into_iter()
+            .map(|(package_name, files)| (package_name, files.into_iter().collect()))
+            .collect())
+    }
+
+    /// Whether results keep tsc's compile order, which only the exact
+    /// calculation reports.
+    fn preserves_compile_order(&self) -> bool {
+        match self.calculation {
+            Calculation::Estimate => false,
+            Calculation::Exact | Calculation::Verified => self.preserve_compile_order,
+        }
+    }
+
+    /// Enumerate the requested packages with the configured [`Calculation`],
+    /// verifying the estimate against the exact calculation when asked to.
+    fn enumeration<T>(&self, monorepo_root: &Path, tsconfig_files: T) -> Result<Enumeration, Error>
+    where
+        T: IntoIterator,
+        T::Item: AsRef<Path>,
+    {
+        let preserve_compile_order = self.preserves_compile_order();
+        let enumeration = match self.calculation {
+            Calculation::Estimate => {
+                estimate::enumerate_detailed(monorepo_root, tsconfig_files, self)?
+            }
+            Calculation::Exact => exact::enumerate_detailed(monorepo_root, tsconfig_files, self)?,
+            Calculation::Verified => {
+                let tsconfig_files: Vec<PathBuf> = tsconfig_files
+                    .into_iter()
+                    .map(|tsconfig_file| tsconfig_file.as_ref().to_owned())
+                    .collect();
+                let estimated = estimate::enumerate(monorepo_root, &tsconfig_files, self)?;
+                let exact = exact::enumerate_detailed(monorepo_root, &tsconfig_files, self)?;
+                self.verify(
+                    group_by_package_name(estimated, false),
+                    group_by_package_name(exact.included_files.clone(), preserve_compile_order),
+                    |package_name| package_name.clone(),
+                )?;
+                exact
+            }
+        };
+        Ok(enumeration)
+    }
+
+    /// Determine whether compiling `tsconfig_file` includes `file`, without
+    /// enumerating every included file when it can be avoided.
+    ///
+    /// With [`Calculation::Verified`], both methods are consulted and their
+    /// answers must agree. A file dropped by the configured path transform
+    /// is not included, while [`Self::package_overrides`] do not apply, as
+    /// with results grouped by tsconfig.
+    ///
+    /// - `monorepo_root` may be an absolute path
+    /// - `tsconfig_file` and `file` should be relative paths from the monorepo
+    ///   root
+    pub fn package_includes_file<P, Q, R>(
+        &self,
+        monorepo_root: P,
+        tsconfig_file: Q,
+        file: R,
+    ) -> Result<bool, Error>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+        R: AsRef<Path>,
+    {
+        let (monorepo_root, tsconfig_file, file) = (
+            monorepo_root.as_ref(),
+            tsconfig_file.as_ref(),
+            file.as_ref(),
+        );
+        let includes_file = match self.calculation {
+            Calculation::Estimate => estimate::package_includes_file_with_options(
+                monorepo_root,
+                tsconfig_file,
+                file,
+                self,
+            )?,
+            Calculation::Exact => {
+                exact::package_includes_file_with_options(monorepo_root, tsconfig_file, file, self)?
+            }
+            Calculation::Verified => {
+                let estimated = estimate::package_includes_file_with_options(
+                    monorepo_root,
+                    tsconfig_file,
+                    file,
+                    self,
+                )?;
+                let exact = exact::package_includes_file_with_options(
+                    monorepo_root,
+                    tsconfig_file,
+                    file,
+                    self,
+                )?;
+                if estimated != exact {
+                    let only_in = |included: bool| {
+                        if included {
+                            vec![file.to_owned()]
+                        } else {
+                            Vec::new()
+                        }
+                    };
+                    return Err(Error {
+                        kind: ErrorKind::Discrepancy(vec![Discrepancy {
+                            group: tsconfig_file.to_string_lossy().into_owned(),
+                            only_in_estimate: only_in(estimated),
+                            only_in_exact: only_in(exact),
+                        }]),
+                    });
+                }
+                exact
+            }
+        };
+        Ok(includes_file && self.keeps_file(tsconfig_file, file))
+    }
+
+    /// Count the files included by each package, grouped by scoped package
+    /// name, without holding the full list of paths for a package in memory
+    /// when it can be avoided.
+    ///
+    /// With [`Calculation::Verified`], the files are enumerated and verified
+    /// as by [`Self::enumerate`] before being counted.
+    ///
+    /// - `monorepo_root` may be an absolute path
+    /// - `tsconfig_files` should be relative paths from the monorepo root
+    pub fn enumerate_counts<P, T>(
+        &self,
+        monorepo_root: P,
+        tsconfig_files: T,
+    ) -> Result<HashMap<String, usize>, Error>
+    where
+        P: AsRef<Path> + Sync,
+        T: IntoIterator,
+        T::Item: AsRef<Path>,
+    {
+        let counts = match self.calculation {
+            Calculation::Estimate => {
+                estimate::tsconfig_include_counts_by_package_name_with_options(
+                    monorepo_root,
+                    tsconfig_files,
+                    self,
+                )?
+            }
+            Calculation::Exact => exact::tsconfig_include_counts_by_package_name_with_options(
+                monorepo_root,
+                tsconfig_files,
+                self,
+            )?,
+            Calculation::Verified => self
+                .enumerate(monorepo_root, tsconfig_files)?
+                .into_iter()
+                .map(|(package_name, files)| (package_name, files.len()))
+                .collect(),
+        };
+        Ok(counts)
+    }
+
+    /// Enumerate tsconfig files spread across several independent monorepos
+    /// in a single call, sharing one thread pool. The return value maps each
+    /// monorepo root, as given, to the result of [`Self::enumerate`] for the
+    /// tsconfig files paired with it.
+    ///
+    /// Each tsconfig's internal dependencies are resolved against its own
+    /// monorepo's manifest, so packages sharing a name across monorepos never
+    /// mix.
+    ///
+    /// - `monorepo_roots` pairs a monorepo root, which may be an absolute
+    ///   path, with tsconfig files given as relative paths from that root
+    pub fn enumerate_monorepos<I, P, T>(
+        &self,
+        monorepo_roots: I,
+    ) -> Result<HashMap<PathBuf, HashMap<String, Vec<PathBuf>>>, Error>
+    where
+        I: IntoIterator<Item = (P, T)>,
+        P: Into<PathBuf>,
+        T: IntoIterator,
+        T::Item: AsRef<Path>,
+    {
+        let mut tsconfig_files_by_monorepo_root: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+        for (monorepo_root, tsconfig_files) in monorepo_roots {
+            tsconfig_files_by_monorepo_root
+                .entry(monorepo_root.into())
+                .or_default()
+                .extend(
+                    tsconfig_files
+                        .into_iter()
+                        .map(|tsconfig_file| tsconfig_file.as_ref().to_owned()),
+                );
+        }
+
+        tsconfig_files_by_monorepo_root
+            .into_par_iter()
+            .map(|(monorepo_root, tsconfig_files)| {
+                let included_files = self.enumerate(&monorepo_root, &tsconfig_files)?;
+                Ok((monorepo_root, included_files))
+            })
+            .collect()
+    }
+
+    /// Return the `exact` result, or describe every group whose files differ
+    /// from the `estimated` result by paths other than the known-divergent
+    /// ones.
+    fn verify<K, F>(
+        &self,
+        mut estimated: HashMap<K, Vec<PathBuf>>,
+        exact: HashMap<K, Vec<PathBuf>>,
+        describe: F,
+    ) -> Result<HashMap<K, Vec<PathBuf>>, Error>
+    where
+        K: Eq + Hash,
+        F: Fn(&K) -> String,
+    {
+        let mut discrepancies: Vec<Discrepancy> = Vec::new();
+        let mut compare = |group: &K, estimated: &[PathBuf], exact: &[PathBuf]| {
+            let estimated: BTreeSet<&PathBuf> = estimated.iter().collect();
+            let exact: BTreeSet<&PathBuf> = exact.iter().collect();
+            let only_in = |a: &BTreeSet<&PathBuf>, b: &BTreeSet<&PathBuf>| -> Vec<PathBuf> {
+                a.difference(b)
+                    .filter(|path| !self.known_divergent_paths.contains(**path))
+                    .map(|path| path.to_path_buf())
+                    .collect()
+            };
+            let only_in_estimate = only_in(&estimated, &exact);
+            let only_in_exact = only_in(&exact, &estimated);
+            if !only_in_estimate.is_empty() || !only_in_exact.is_empty() {
+                discrepancies.push(Discrepancy {
+                    group: describe(group),
+                    only_in_estimate,
+                    only_in_exact,
+                });
+            }
+        };
+
+        for (group, exact_files) in &exact {
+            let estimated_files = estimated.remove(group).unwrap_or_default();
+            compare(group, &estimated_files, exact_files);
+        }
+        for (group, estimated_files) in &estimated {
+            compare(group, estimated_files, &[]);
+        }
+
+        if discrepancies.is_empty() {
+            return Ok(exact);
+        }
+        discrepancies.sort_unstable_by(|a, b| a.group.cmp(&b.group));
+        Err(Error {
+            kind: ErrorKind::Discrepancy(discrepancies),
+        })
+    }
+}
+
+/// Paths on which the estimate and exact calculations disagree for one
+/// group of results.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Discrepancy {
+    /// Scoped package name, or tsconfig path when enumerating by tsconfig.
+    pub group: String,
+    /// Alphabetically-sorted paths only present in the estimate.
+    pub only_in_estimate: Vec<PathBuf>,
+    /// Alphabetically-sorted paths only present in the exact result.
+    pub only_in_exact: Vec<PathBuf>,
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct Error {
+    kind: ErrorKind,
+}
+
+impl Error {
+    /// What went wrong, for callers that need to tell failures apart.
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ErrorKind::Estimate(_) => write!(f, "estimate enumeration failed"),
+            ErrorKind::Exact(_) => write!(f, "exact enumeration failed"),
+            ErrorKind::Digest(path, _) => write!(f, "unable to digest file {:?}", path),
+            ErrorKind::Discrepancy(discrepancies) => {
+                write!(f, "estimate and exact enumerations disagree")?;
+                for discrepancy in discrepancies {
+                    write!(
+                        f,
+                        "\n{}: only in estimate {:?}, only in exact {:?}",
+                        discrepancy.group, discrepancy.only_in_estimate, discrepancy.only_in_exact
+                    )?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ErrorKind::Estimate(err) => Some(err),
+            ErrorKind::Exact(err) => Some(err),
+            ErrorKind::Digest(_, err) => Some(err),
+            ErrorKind::Discrepancy(_) => None,
+        }
+    }
+}
+
+impl From<estimate::Error> for Error {
+    fn from(err: estimate::Error) -> Self {
+        Self {
+            kind: ErrorKind::Estimate(err),
+        }
+    }
+}
+
+impl From<exact::Error> for Error {
+    fn from(err: exact::Error) -> Self {
+        Self {
+            kind: ErrorKind::Exact(err),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ErrorKind {
+    #[non_exhaustive]
+    Estimate(estimate::Error),
+    #[non_exhaustive]
+    Exact(exact::Error),
+    #[non_exhaustive]
+    Discrepancy(Vec<Discrepancy>),
+    #[non_exhaustive]
+    Digest(PathBuf, io::Error),
+}
+
+/// Build the tree of internal dependencies beneath `package_name`, skipping
+/// any dependency among its `ancestors` to cut cycles.
+fn dependency_tree(
+    package_name: &str,
+    included_files: &HashMap<String, Vec<PathBuf>>,
+    dependency_graph: &DependencyGraph,
+    ancestors: &mut Vec<String>,
+) -> DependencyTree {
+    ancestors.push(package_name.to_owned());
+    let mut dependencies = BTreeMap::new();
+    for dependency in dependency_graph
+        .internal_dependencies
+        .get(package_name)
+        .into_iter()
+        .flatten()
+    {
+        if !ancestors.contains(dependency) {
+            let tree = dependency_tree(dependency, included_files, dependency_graph, ancestors);
+            dependencies.insert(dependency.clone(), tree);
+        }
+    }
+    ancestors.pop();
+    DependencyTree {
+        files: included_files
+            .get(package_name)
+            .cloned()
+            .unwrap_or_default(),
+        dependencies,
+    }
+}
@@ -1,96 +1,107 @@
-use std::{
-    error::Error,
-    fmt::Display,
-    path::{self, Path, PathBuf},
-};
+use std::path::{Component, Path, PathBuf};
 
-#[derive(Debug)]
-#[non_exhaustive]
-pub struct StripPrefixError {
-    absolute_path: PathBuf,
-    kind: StripPrefixErrorKind,
+/// Express `file`, an absolute path as reported by tsc, as a relative path
+/// from `monorepo_root`, which must be canonical. Returns `None` for files
+/// outside the monorepo or beneath a `node_modules` directory.
+///
+/// This sits in the hot loop over tsc's output, so the constant prefix is
+/// stripped once rather than matched against every ancestor, and the result
+/// borrows from `file` instead of allocating.
+pub(crate) fn monorepo_relative_path<'a>(monorepo_root: &Path, file: &'a Path) -> Option<&'a Path> {
+    if is_child_of_node_modules(file) {
+        return None;
+    }
+    file.strip_prefix(monorepo_root).ok()
 }
 
-impl Display for StripPrefixError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match &self.kind {
-            StripPrefixErrorKind::Strip { ancestor, inner: _ } => write!(
-                f,
-                "cannot strip prefix {:?} from path {:?}",
-                ancestor, self.absolute_path
-            ),
-            StripPrefixErrorKind::PrefixNotFound { prefix } => write!(
-                f,
-                "never encountered prefix {:?} in path {:?}",
-                prefix, self.absolute_path
-            ),
-        }
-    }
+pub(crate) fn is_glob(string: &str) -> bool {
+    string.contains('*')
 }
 
-impl Error for StripPrefixError {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        match &self.kind {
-            StripPrefixErrorKind::Strip { ancestor: _, inner } => Some(inner),
-            StripPrefixErrorKind::PrefixNotFound { prefix: _ } => None,
-        }
+/// Rewrite `include` patterns into globs relative to the tsconfig directory.
+///
+/// The `${configDir}` template variable introduced in TypeScript 5.5 refers
+/// to the directory of the leaf tsconfig, which is the directory we resolve
+/// globs against, so it is replaced with `.`.
+///
+/// Directory-style patterns are expanded the way tsc does, so that `src`,
+/// `src/`, and `src/**` all behave like `src/**/*`. From the [tsconfig
+/// include] documentation: if the last path segment in a pattern does not
+/// contain a file extension or wildcard character, then it is treated as a
+/// directory, and files with supported extensions inside that directory are
+/// included.
+///
+/// [tsconfig include]: https://www.typescriptlang.org/tsconfig#include
+pub(crate) fn normalize_include_pattern(pattern: &str) -> String {
+    let pattern = pattern.replace("${configDir}", ".");
+    let mut pattern = pattern.trim_end_matches('/');
+    while let Some(stripped) = pattern.strip_prefix("./") {
+        pattern = stripped;
+    }
+    if pattern.is_empty() || pattern == "." {
+        return String::from("**/*");
     }
-}
 
-#[derive(Debug)]
-pub enum StripPrefixErrorKind {
-    #[non_exhaustive]
-    Strip {
-        ancestor: PathBuf,
-        inner: path::StripPrefixError,
-    },
-    #[non_exhaustive]
-    PrefixNotFound { prefix: PathBuf },
+    let last_segment = pattern.rsplit('/').next().unwrap_or(pattern);
+    if last_segment == "**" {
+        format!("{}/*", pattern)
+    } else if !last_segment.contains(['*', '?', '.']) {
+        format!("{}/**/*", pattern)
+    } else {
+        pattern.to_owned()
+    }
 }
 
-// DISCUSS: can this function name be improved?
-pub(crate) fn remove_relative_path_prefix_from_absolute_path(
-    prefix: &Path,
-    absolute_path: &Path,
-) -> Result<PathBuf, StripPrefixError> {
-    (|| {
-        for ancestor in absolute_path.ancestors() {
-            if ancestor.ends_with(prefix) {
-                let relative_path = absolute_path
-                    .strip_prefix(ancestor)
-                    .map(ToOwned::to_owned)
-                    .map_err(|inner| StripPrefixErrorKind::Strip {
-                        ancestor: ancestor.to_owned(),
-                        inner,
-                    })?;
-                return Ok(relative_path);
+/// Expand brace alternatives, so that `src/**/*.{ts,tsx}` becomes
+/// `src/**/*.ts` and `src/**/*.tsx`. Nested braces expand recursively, and a
+/// pattern without a balanced brace pair is returned unchanged.
+pub(crate) fn expand_braces(pattern: &str) -> Vec<String> {
+    let Some(open) = pattern.find('{') else {
+        return vec![pattern.to_owned()];
+    };
+
+    let mut depth = 0;
+    let mut alternatives = Vec::new();
+    let mut alternative_start = open + 1;
+    for (index, c) in pattern
+        .char_indices()
+        .skip_while(|(index, _)| *index <= open)
+    {
+        match c {
+            '{' => depth += 1,
+            '}' if depth > 0 => depth -= 1,
+            ',' if depth == 0 => {
+                alternatives.push(&pattern[alternative_start..index]);
+                alternative_start = index + 1;
             }
+            '}' => {
+                alternatives.push(&pattern[alternative_start..index]);
+                let (prefix, suffix) = (&pattern[..open], &pattern[index + 1..]);
+                return alternatives
+                    .into_iter()
+                    .flat_map(|alternative| {
+                        expand_braces(&format!("{}{}{}", prefix, alternative, suffix))
+                    })
+                    .collect();
+            }
+            _ => {}
         }
-
-        return Err(StripPrefixErrorKind::PrefixNotFound {
-            prefix: prefix.to_owned(),
-        })?;
-    })()
-    .map_err(|kind| StripPrefixError {
-        absolute_path: absolute_path.to_owned(),
-        kind,
-    })
-}
-
-pub(crate) fn is_glob(string: &str) -> bool {
-    string.contains('*')
+    }
+    vec![pattern.to_owned()]
 }
 
+/// The file extension named by `glob`: the literal suffix after the last
+/// wildcard in its final path segment, starting from the first `.`. So
+/// `src/**/*.test.ts` names `.test.ts`, while `src/**/*` and
+/// `src/**/index.ts` name none.
+///
+/// A glob's extension only admits files through that glob; see
+/// `TypescriptConfig::included_file_types`.
 pub(crate) fn glob_file_extension(glob: &str) -> Option<String> {
-    if glob.ends_with('*') {
-        return None;
-    }
-    Some(
-        glob.rsplit('*')
-            .next()
-            .expect("Expected glob to contain an asterisk")
-            .to_owned(),
-    )
+    let last_segment = glob.rsplit('/').next().unwrap_or(glob);
+    let (_, suffix) = last_segment.rsplit_once(['*', '?'])?;
+    let extension_start = suffix.find('.')?;
+    Some(suffix[extension_start..].to_owned())
 }
 
 pub(crate) fn is_monorepo_file(monorepo_root: &Path, file: &Path) -> bool {
@@ -103,10 +114,43 @@ pub(crate) fn is_monorepo_file(monorepo_root: &Path, file: &Path) -> bool {
 }
 
 pub(crate) fn is_child_of_node_modules(file: &Path) -> bool {
-    for ancestor in file.ancestors() {
-        if ancestor.ends_with("node_modules") {
-            return true;
+    file.components()
+        .any(|component| component == Component::Normal("node_modules".as_ref()))
+}
+
+/// Lexically resolve `.` and `..` components without touching the filesystem.
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => {}
+                _ => normalized.push(component),
+            },
+            _ => normalized.push(component),
         }
     }
-    false
+    normalized
+}
+
+/// Compute the relative path that leads from directory `from` to `to`.
+///
+/// Both paths are expected to be absolute (or relative to the same base).
+pub(crate) fn relative_path(from: &Path, to: &Path) -> PathBuf {
+    let from = normalize(from);
+    let to = normalize(to);
+    let common = from
+        .components()
+        .zip(to.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    from.components()
+        .skip(common)
+        .map(|_| Component::ParentDir)
+        .chain(to.components().skip(common))
+        .collect()
 }
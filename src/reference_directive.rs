@@ -0,0 +1,196 @@
+//! Follow triple-slash `/// <reference ... />` directives in estimate mode.
+//!
+//! tsc only honors reference directives in the leading comment block of a
+//! file, so scanning stops at the first line that is neither blank nor a
+//! comment. `path=` references resolve relative to the referencing file and
+//! `types=` references resolve to an `@types` package found by walking up
+//! through `node_modules` directories.
+//!
+//! LIMITATION: `types=` references are only reported when they resolve
+//! (through a symbolic link, as with workspace packages) to a file inside the
+//! monorepo, since files under `node_modules` are never enumerated.
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::{
+    io::read_json_from_file,
+    path::{is_child_of_node_modules, normalize},
+};
+
+/// Extensions tsc tries when a `path=` reference omits one.
+const REFERENCE_PATH_EXTENSIONS: [&str; 3] = [".ts", ".tsx", ".d.ts"];
+
+#[derive(Debug, PartialEq, Eq)]
+enum ReferenceDirective<'a> {
+    Path(&'a str),
+    Types(&'a str),
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TypesPackageManifest {
+    types: Option<PathBuf>,
+    typings: Option<PathBuf>,
+}
+
+/// Read the value of attribute `name` from the body of a reference directive.
+fn attribute<'a>(directive: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = directive;
+    while let Some(index) = rest.find(name) {
+        let is_attribute_name = rest[..index].ends_with(char::is_whitespace);
+        rest = &rest[index + name.len()..];
+        let Some(value) = rest.trim_start().strip_prefix('=') else {
+            continue;
+        };
+        if !is_attribute_name {
+            continue;
+        }
+        let value = value.trim_start();
+        let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let value = &value[1..];
+        return value.find(quote).map(|end| &value[..end]);
+    }
+    None
+}
+
+/// Parse the reference directives from the leading comment block of `contents`.
+fn leading_reference_directives(contents: &str) -> Vec<ReferenceDirective<'_>> {
+    let mut directives = Vec::new();
+    let mut in_block_comment = false;
+    for line in contents.lines() {
+        let line = line.trim();
+        if in_block_comment {
+            in_block_comment = !line.contains("*/");
+            continue;
+        }
+        if line.is_empty() || line.starts_with("#!") {
+            continue;
+        }
+        if let Some(comment) = line.strip_prefix("/*") {
+            in_block_comment = !comment.contains("*/");
+            continue;
+        }
+        let Some(comment) = line.strip_prefix("//") else {
+            break;
+        };
+        let Some(directive) = comment
+            .strip_prefix('/')
+            .map(str::trim_start)
+            .and_then(|directive| directive.strip_prefix("<reference"))
+        else {
+            continue;
+        };
+        if let Some(path) = attribute(directive, "path") {
+            directives.push(ReferenceDirective::Path(path));
+        } else if let Some(types) = attribute(directive, "types") {
+            directives.push(ReferenceDirective::Types(types));
+        }
+    }
+    directives
+}
+
+fn resolve_path_reference(referencing_directory: &Path, reference: &str) -> Option<PathBuf> {
+    let candidate = normalize(&referencing_directory.join(reference));
+    if candidate.is_file() {
+        return Some(candidate);
+    }
+    REFERENCE_PATH_EXTENSIONS
+        .iter()
+        .map(|extension| {
+            let mut candidate = candidate.clone().into_os_string();
+            candidate.push(extension);
+            PathBuf::from(candidate)
+        })
+        .find(|candidate| candidate.is_file())
+}
+
+fn resolve_types_reference(referencing_directory: &Path, reference: &str) -> Option<PathBuf> {
+    // @scope/name is published to DefinitelyTyped as @types/scope__name
+    let types_package_name = match reference.strip_prefix('@') {
+        Some(scoped) => scoped.replacen('/', "__", 1),
+        None => reference.to_owned(),
+    };
+    let referencing_directory = fs::canonicalize(referencing_directory).ok()?;
+    referencing_directory.ancestors().find_map(|directory| {
+        let package_directory = directory
+            .join("node_modules")
+            .join("@types")
+            .join(&types_package_name);
+        if !package_directory.is_dir() {
+            return None;
+        }
+        let manifest: TypesPackageManifest =
+            read_json_from_file(package_directory.join("package.json")).unwrap_or_default();
+        let entry_point = manifest
+            .types
+            .or(manifest.typings)
+            .unwrap_or_else(|| PathBuf::from("index.d.ts"));
+        fs::canonicalize(package_directory.join(entry_point))
+            .ok()
+            .filter(|path| path.is_file())
+    })
+}
+
+/// Extend `included_files`, given as relative paths from the monorepo root,
+/// with every monorepo file transitively reachable through their reference
+/// directives.
+pub(crate) fn follow_reference_directives(
+    monorepo_root: &Path,
+    included_files: Vec<PathBuf>,
+) -> Vec<PathBuf> {
+    let normalized_monorepo_root = normalize(monorepo_root);
+    let canonical_monorepo_root = fs::canonicalize(monorepo_root).ok();
+    let mut seen: HashSet<PathBuf> = included_files.iter().cloned().collect();
+    let mut pending = included_files.clone();
+    let mut included_files = included_files;
+
+    while let Some(file) = pending.pop() {
+        let path = monorepo_root.join(&file);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) => {
+                debug!(?path, error = %err, "unable to scan file for reference directives");
+                continue;
+            }
+        };
+        let referencing_directory = path.parent().unwrap_or(monorepo_root);
+
+        for directive in leading_reference_directives(&contents) {
+            let referenced_file = match directive {
+                ReferenceDirective::Path(reference) => {
+                    resolve_path_reference(referencing_directory, reference).and_then(|resolved| {
+                        resolved
+                            .strip_prefix(&normalized_monorepo_root)
+                            .ok()
+                            .map(ToOwned::to_owned)
+                    })
+                }
+                ReferenceDirective::Types(reference) => {
+                    resolve_types_reference(referencing_directory, reference).and_then(|resolved| {
+                        let root = canonical_monorepo_root.as_deref()?;
+                        resolved.strip_prefix(root).ok().map(ToOwned::to_owned)
+                    })
+                }
+            };
+            let Some(referenced_file) = referenced_file else {
+                debug!(file = ?file, ?directive, "unable to resolve reference directive");
+                continue;
+            };
+            if is_child_of_node_modules(&referenced_file) {
+                continue;
+            }
+            if seen.insert(referenced_file.clone()) {
+                included_files.push(referenced_file.clone());
+                pending.push(referenced_file);
+            }
+        }
+    }
+
+    included_files
+}
@@ -0,0 +1,41 @@
+//! Plain-text rendering of errors along with their causes.
+
+use std::{error::Error, fmt::Display};
+
+/// Display an error followed by its transitive [`source`](Error::source)
+/// chain, one cause per line, each indented one level deeper than the error
+/// it caused:
+///
+/// ```text
+/// unable to estimate tsconfig includes
+///   caused by: unable to read file "packages/foo/tsconfig.json"
+///     caused by: No such file or directory (os error 2)
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct ErrorChain<'a>(pub &'a (dyn Error + 'static));
+
+impl Display for ErrorChain<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)?;
+        let mut depth = 1;
+        let mut source = self.0.source();
+        while let Some(cause) = source {
+            write!(
+                f,
+                "\n{:indent$}caused by: {}",
+                "",
+                cause,
+                indent = depth * 2
+            )?;
+            depth += 1;
+            source = cause.source();
+        }
+        Ok(())
+    }
+}
+
+/// Render `err` and its transitive cause chain as indented lines, as
+/// displayed by [`ErrorChain`].
+pub fn render_error_chain(err: &(dyn Error + 'static)) -> String {
+    ErrorChain(err).to_string()
+}
@@ -0,0 +1,267 @@
+//! Enumeration results carrying metadata beyond the included files.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    iter::FusedIterator,
+    path::{Path, PathBuf},
+    slice, vec,
+};
+
+use crate::estimate::has_file_extension;
+
+/// The files included by each package, along with any soft signals about
+/// likely misconfiguration noticed along the way.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct EnumerationResult {
+    /// Relative paths (see [`PathStyle`](crate::options::PathStyle)) grouped
+    /// by scoped package name.
+    pub included_files: HashMap<String, Vec<PathBuf>>,
+    /// Alphabetically-sorted relative paths from the monorepo root to the
+    /// tsconfig files each package was enumerated through, grouped by scoped
+    /// package name.
+    pub tsconfig_files: HashMap<String, Vec<PathBuf>>,
+    /// Alphabetically-sorted warnings about the enumerated packages.
+    pub warnings: Vec<Warning>,
+    /// Alphabetically-sorted scoped names of internal dependencies that were
+    /// skipped because they have a package.json but no tsconfig.json.
+    pub packages_without_tsconfig: Vec<String>,
+    /// Hex-encoded SHA-256 digest of each package's files, grouped by scoped
+    /// package name. Empty unless requested with
+    /// [`EnumerationOptions::digest`](crate::options::EnumerationOptions::digest).
+    pub digests: HashMap<String, String>,
+}
+
+impl EnumerationResult {
+    /// Iterate over every included file as a `(package, file)` pair, see
+    /// [`files_by_package`].
+    pub fn files(&self) -> IncludedFiles<'_> {
+        files_by_package(&self.included_files)
+    }
+}
+
+/// The kind of an included file, as told by its extension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum FileKind {
+    /// TypeScript source, like `.ts` or `.tsx`.
+    Source,
+    /// A declaration file, like `.d.ts`, including the declaration shims for
+    /// arbitrary extensions, like `styles.d.css.ts`.
+    Declaration,
+    /// JavaScript, like `.js` or `.jsx`, included under `allowJs`.
+    JavaScript,
+    /// A JSON module, included under `resolveJsonModule`.
+    Json,
+    /// Any other file, like the `.vue` files an alternate compiler lists.
+    Other,
+}
+
+impl FileKind {
+    /// Classify `file` by its extension. Declaration extensions are checked
+    /// before source extensions, which they end with.
+    pub fn of<P: AsRef<Path>>(file: P) -> Self {
+        const KINDS: [(FileKind, &[&str]); 4] = [
+            (
+                FileKind::Declaration,
+                &[".d.ts", ".d.mts", ".d.cts", ".d.*.ts"],
+            ),
+            (FileKind::Source, &[".ts", ".tsx", ".mts", ".cts"]),
+            (FileKind::JavaScript, &[".js", ".jsx", ".mjs", ".cjs"]),
+            (FileKind::Json, &[".json"]),
+        ];
+        let Some(file) = file.as_ref().to_str() else {
+            return FileKind::Other;
+        };
+        KINDS
+            .iter()
+            .find(|(_, extensions)| {
+                extensions
+                    .iter()
+                    .any(|extension| has_file_extension(file, extension))
+            })
+            .map_or(FileKind::Other, |(kind, _)| *kind)
+    }
+}
+
+/// A package's included files, partitioned by [`FileKind`], as reported by
+/// [`EnumerationOptions::enumerate_partitioned`](crate::options::EnumerationOptions::enumerate_partitioned).
+/// Each bucket keeps the order of the files it was partitioned from.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PartitionedFiles {
+    /// Files of [`FileKind::Source`].
+    pub source: Vec<PathBuf>,
+    /// Files of [`FileKind::Declaration`].
+    pub declarations: Vec<PathBuf>,
+    /// Files of [`FileKind::JavaScript`].
+    pub javascript: Vec<PathBuf>,
+    /// Files of [`FileKind::Json`].
+    pub json: Vec<PathBuf>,
+    /// Files of [`FileKind::Other`].
+    pub other: Vec<PathBuf>,
+}
+
+impl PartitionedFiles {
+    /// The bucket holding files of `kind`.
+    pub fn of_kind(&self, kind: FileKind) -> &[PathBuf] {
+        match kind {
+            FileKind::Source => &self.source,
+            FileKind::Declaration => &self.declarations,
+            FileKind::JavaScript => &self.javascript,
+            FileKind::Json => &self.json,
+            FileKind::Other => &self.other,
+        }
+    }
+}
+
+impl FromIterator<PathBuf> for PartitionedFiles {
+    fn from_iter<I: IntoIterator<Item = PathBuf>>(files: I) -> Self {
+        let mut partitioned = Self::default();
+        for file in files {
+            let bucket = match FileKind::of(&file) {
+                FileKind::Source => &mut partitioned.source,
+                FileKind::Declaration => &mut partitioned.declarations,
+                FileKind::JavaScript => &mut partitioned.javascript,
+                FileKind::Json => &mut partitioned.json,
+                FileKind::Other => &mut partitioned.other,
+            };
+            bucket.push(file);
+        }
+        partitioned
+    }
+}
+
+/// The files included by a package, along with the internal dependencies it
+/// pulled into the enumeration, as reported by
+/// [`EnumerationOptions::enumerate_tree`](crate::options::EnumerationOptions::enumerate_tree).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DependencyTree {
+    /// Relative paths (see [`PathStyle`](crate::options::PathStyle)) of the
+    /// files included by this package.
+    pub files: Vec<PathBuf>,
+    /// The trees of this package's internal dependencies, by scoped package
+    /// name.
+    pub dependencies: BTreeMap<String, DependencyTree>,
+}
+
+/// Iterate over the files in `grouped`, as returned by any of the enumeration
+/// functions, as `(package, file)` pairs without copying them.
+///
+/// Packages are visited in alphabetical order, and each package's files in
+/// the order they were enumerated, so the traversal order is stable across
+/// runs.
+pub fn files_by_package<K>(grouped: &HashMap<K, Vec<PathBuf>>) -> IncludedFiles<'_>
+where
+    K: AsRef<str>,
+{
+    let mut packages: Vec<(&str, &[PathBuf])> = grouped
+        .iter()
+        .map(|(package, files)| (package.as_ref(), files.as_slice()))
+        .collect();
+    packages.sort_unstable_by_key(|(package, _)| *package);
+    let remaining = packages.iter().map(|(_, files)| files.len()).sum();
+    IncludedFiles {
+        packages: packages.into_iter(),
+        current: None,
+        remaining,
+    }
+}
+
+/// Iterator over `(package, file)` pairs, created by [`files_by_package`] and
+/// [`EnumerationResult::files`].
+#[derive(Clone, Debug)]
+pub struct IncludedFiles<'a> {
+    packages: vec::IntoIter<(&'a str, &'a [PathBuf])>,
+    current: Option<(&'a str, slice::Iter<'a, PathBuf>)>,
+    remaining: usize,
+}
+
+impl<'a> Iterator for IncludedFiles<'a> {
+    type Item = (&'a str, &'a Path);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((package, files)) = &mut self.current {
+                if let Some(file) = files.next() {
+                    self.remaining -= 1;
+                    return Some((*package, file.as_path()));
+                }
+            }
+            let (package, files) = self.packages.next()?;
+            self.current = Some((package, files.iter()));
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for IncludedFiles<'_> {}
+
+impl FusedIterator for IncludedFiles<'_> {}
+
+/// A condition that does not prevent enumeration, but that callers may want
+/// to flag.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum Warning {
+    /// The package's tsconfig declares neither `include` nor `files`, and no
+    /// files exist beneath its directory, so it enumerates nothing.
+    EmptyProject { package: String },
+    /// tsc exited with a non-zero status while enumerating the package in
+    /// [best-effort](crate::options::EnumerationOptions::best_effort) mode,
+    /// so its files are whatever tsc listed before failing.
+    CompilerDiagnostics { package: String, stderr: String },
+    /// The package declares a dependency through the workspace protocol, like
+    /// `"workspace:^"`, on a package the monorepo does not contain, so that
+    /// dependency's files are missing from the results. Only reported when
+    /// [missing internal dependencies are
+    /// allowed](crate::options::EnumerationOptions::allow_missing_internal_dependencies).
+    MissingInternalDependency { dependent: String, missing: String },
+    /// The compiler or a `.tsbuildinfo` listed a file, given as a relative
+    /// path from the monorepo root, that does not exist on disk, so it was
+    /// dropped from the package's files. Only reported when [checking that
+    /// files exist](crate::options::EnumerationOptions::check_files_exist).
+    MissingEnumeratedFile { package: String, file: PathBuf },
+    /// The package's [override](crate::options::PackageOverride) removes a
+    /// file, expressed in the configured
+    /// [`PathStyle`](crate::options::PathStyle), that the package did not
+    /// include, so the override may be stale.
+    UnmatchedOverride { package: String, file: PathBuf },
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Warning::EmptyProject { package } => write!(
+                f,
+                "package {} declares no include or files and enumerates nothing",
+                package
+            ),
+            Warning::CompilerDiagnostics { package, stderr } => write!(
+                f,
+                "tsc exited with non-zero status for package {}, its files may be incomplete:\n{}",
+                package, stderr
+            ),
+            Warning::MissingInternalDependency { dependent, missing } => write!(
+                f,
+                "package {} depends on {}, which is not a package in the monorepo",
+                dependent, missing
+            ),
+            Warning::MissingEnumeratedFile { package, file } => write!(
+                f,
+                "package {} lists {:?}, which does not exist",
+                package, file
+            ),
+            Warning::UnmatchedOverride { package, file } => write!(
+                f,
+                "the override for package {} removes {:?}, which it does not include",
+                package, file
+            ),
+        }
+    }
+}
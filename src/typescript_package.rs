@@ -1,62 +1,491 @@
-use std::path::{Path, PathBuf};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    hash::{Hash, Hasher},
+    iter,
+    path::{Path, PathBuf},
+};
 
+use rayon::prelude::*;
 use serde::Deserialize;
+use typescript_tools::{
+    configuration_file::ConfigurationFile,
+    monorepo_manifest::{EnumeratePackageManifestsError, MonorepoManifest},
+    package_manifest::PackageManifest as MonorepoPackageManifest,
+};
 
-use crate::io::{read_json_from_file, FromFileError};
+use crate::{
+    io::{read_json_from_file, FromFileError},
+    options::{DependencyDepth, EnumerationOptions},
+    path::normalize,
+    result::Warning,
+};
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// A package to enumerate, identified by the canonical path to its tsconfig
+/// file so the same tsconfig reached through different relative paths is
+/// only enumerated once.
+#[derive(Clone, Debug)]
 pub(crate) struct TypescriptPackage {
     pub scoped_package_name: String,
+    /// Relative path from the monorepo root.
     pub tsconfig_file: TypescriptConfigFile,
+    /// Relative path from the monorepo root.
+    pub package_directory: PathBuf,
+    canonical_tsconfig_file: PathBuf,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
-pub(crate) struct PackageManifestFile(PathBuf);
+impl TypescriptPackage {
+    pub fn new(
+        monorepo_root: &Path,
+        scoped_package_name: String,
+        tsconfig_file: TypescriptConfigFile,
+        package_directory: &Path,
+    ) -> Self {
+        let tsconfig_path = monorepo_root.join(tsconfig_file.as_path());
+        // A tsconfig that does not exist cannot be canonicalized, but will
+        // produce a descriptive error once we try to read it
+        let canonical_tsconfig_file =
+            std::fs::canonicalize(&tsconfig_path).unwrap_or_else(|_| normalize(&tsconfig_path));
+        Self {
+            scoped_package_name,
+            tsconfig_file: TypescriptConfigFile(normalize(tsconfig_file.as_path())),
+            package_directory: normalize(package_directory),
+            canonical_tsconfig_file,
+        }
+    }
 
-impl From<PathBuf> for PackageManifestFile {
-    fn from(path: PathBuf) -> Self {
-        Self(path)
+    /// A package whose tsconfig file sits beside its `package.json`, in its
+    /// package directory.
+    pub fn beside_package_manifest(
+        monorepo_root: &Path,
+        scoped_package_name: String,
+        tsconfig_file: TypescriptConfigFile,
+    ) -> Self {
+        let package_directory = tsconfig_file
+            .as_path()
+            .parent()
+            .map(ToOwned::to_owned)
+            .unwrap_or_default();
+        Self::new(
+            monorepo_root,
+            scoped_package_name,
+            tsconfig_file,
+            &package_directory,
+        )
+    }
+}
+
+impl PartialEq for TypescriptPackage {
+    fn eq(&self, other: &Self) -> bool {
+        self.canonical_tsconfig_file == other.canonical_tsconfig_file
     }
 }
 
+impl Eq for TypescriptPackage {}
+
+impl Hash for TypescriptPackage {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.canonical_tsconfig_file.hash(state);
+    }
+}
+
+impl PartialOrd for TypescriptPackage {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TypescriptPackage {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.canonical_tsconfig_file
+            .cmp(&other.canonical_tsconfig_file)
+    }
+}
+
+/// The packages to enumerate for a set of requested tsconfig files.
+#[derive(Debug, Default)]
+pub(crate) struct ResolvedPackages {
+    /// Ordered by canonical tsconfig path, so that packages are handed to
+    /// the thread pool, and their results collected, in the same order on
+    /// every run.
+    pub typescript_packages: BTreeSet<TypescriptPackage>,
+    /// Scoped names of internal dependencies skipped because they have no
+    /// tsconfig.json beside their package.json.
+    pub packages_without_tsconfig: BTreeSet<String>,
+    /// Dependent and missing package names of internal dependencies that
+    /// name no package in the monorepo, see [`missing_internal_dependencies`].
+    pub missing_internal_dependencies: BTreeSet<(String, String)>,
+    pub dependency_graph: DependencyGraph,
+}
+
+/// Which requested package pulled in which internal dependency.
+#[derive(Debug, Default)]
+pub(crate) struct DependencyGraph {
+    /// Scoped names of the packages whose tsconfig files were requested.
+    pub requested_packages: BTreeSet<String>,
+    /// Scoped names of the internal dependencies followed from each package,
+    /// grouped by scoped package name. Packages whose dependencies were not
+    /// followed, as with [`DependencyDepth::Direct`], have no entry.
+    pub internal_dependencies: HashMap<String, BTreeSet<String>>,
+}
+
+impl ResolvedPackages {
+    /// Warn about every internal dependency naming no package in the monorepo.
+    pub fn missing_internal_dependency_warnings(&self) -> impl Iterator<Item = Warning> + '_ {
+        self.missing_internal_dependencies
+            .iter()
+            .map(|(dependent, missing)| Warning::MissingInternalDependency {
+                dependent: dependent.clone(),
+                missing: missing.clone(),
+            })
+    }
+}
+
+/// The files included by each enumerated package, along with metadata
+/// gathered along the way.
+#[derive(Debug, Default)]
+pub(crate) struct Enumeration {
+    pub included_files: Vec<(TypescriptPackage, Vec<PathBuf>)>,
+    pub warnings: Vec<Warning>,
+    pub packages_without_tsconfig: BTreeSet<String>,
+    pub dependency_graph: DependencyGraph,
+}
+
+/// Index every package in the monorepo by its scoped package name.
+///
+/// Unlike [`MonorepoManifest::package_manifests_by_package_name`], two
+/// packages declaring the same name are reported instead of one silently
+/// shadowing the other.
+pub(crate) fn package_manifests_by_package_name(
+    monorepo_manifest: &MonorepoManifest,
+) -> Result<HashMap<String, MonorepoPackageManifest>, PackageManifestsError> {
+    let mut package_manifests_by_package_name: HashMap<String, MonorepoPackageManifest> =
+        HashMap::new();
+    for package_manifest in monorepo_manifest
+        .internal_package_manifests()
+        .map_err(EnumeratePackageManifestsError::from)?
+    {
+        let package_manifest = package_manifest.map_err(EnumeratePackageManifestsError::from)?;
+        let package_name = package_manifest.contents.name.clone();
+        if let Some(existing) = package_manifests_by_package_name.get(&package_name) {
+            let mut paths = [existing.path(), package_manifest.path()];
+            paths.sort_unstable();
+            return Err(PackageManifestsError::DuplicatePackageName {
+                name: package_name,
+                paths,
+            });
+        }
+        package_manifests_by_package_name.insert(package_name, package_manifest);
+    }
+    Ok(package_manifests_by_package_name)
+}
+
 #[derive(Debug)]
-pub(crate) struct PackageInMonorepoRootError(pub PathBuf);
+pub(crate) enum PackageManifestsError {
+    Enumerate(EnumeratePackageManifestsError),
+    DuplicatePackageName { name: String, paths: [PathBuf; 2] },
+}
 
-impl TryFrom<TypescriptConfigFile> for PackageManifestFile {
-    type Error = PackageInMonorepoRootError;
+impl From<EnumeratePackageManifestsError> for PackageManifestsError {
+    fn from(err: EnumeratePackageManifestsError) -> Self {
+        Self::Enumerate(err)
+    }
+}
 
-    fn try_from(tsconfig_file: TypescriptConfigFile) -> Result<Self, Self::Error> {
-        let package_directory = tsconfig_file
-            .0
-            .parent()
-            .ok_or_else(|| PackageInMonorepoRootError(tsconfig_file.0.clone()))?;
-        Ok(Self(package_directory.join("package.json")))
+/// The dependencies of `package_manifest` declared with the workspace
+/// protocol, like `"workspace:^"`, that name no package in the monorepo.
+///
+/// Such a dependency can only be satisfied by a package in the monorepo, so
+/// it signals a broken workspace link. Other dependencies are indistinguishable
+/// from external packages and are not checked.
+fn missing_internal_dependencies<'a>(
+    package_manifest: &'a MonorepoPackageManifest,
+    package_manifests_by_package_name: &'a HashMap<String, MonorepoPackageManifest>,
+) -> impl Iterator<Item = (String, String)> + 'a {
+    package_manifest
+        .dependencies_iter()
+        .filter(|(package_name, version)| {
+            version
+                .as_str()
+                .is_some_and(|version| version.starts_with("workspace:"))
+                && !package_manifests_by_package_name.contains_key(*package_name)
+        })
+        .map(|(package_name, _)| {
+            (
+                package_manifest.contents.name.clone(),
+                package_name.to_owned(),
+            )
+        })
+}
+
+/// Resolve the requested tsconfig files, given as relative paths from the
+/// monorepo root, into the set of packages to enumerate: each requested
+/// package plus its internal dependencies, up to `dependency_depth`.
+///
+/// Internal dependencies without a tsconfig.json, like plain JavaScript
+/// packages, are skipped rather than enumerated. Internal dependencies of the
+/// enumerated packages that name no package in the monorepo are reported
+/// rather than failing resolution, so callers can choose how to treat them.
+pub(crate) fn typescript_packages_to_enumerate<T>(
+    monorepo_root: &Path,
+    tsconfig_files: T,
+    package_manifests_by_package_name: &HashMap<String, MonorepoPackageManifest>,
+    options: &EnumerationOptions,
+) -> Result<ResolvedPackages, FromTypescriptConfigFileError>
+where
+    T: IntoIterator,
+    T::Item: AsRef<Path>,
+{
+    let mut typescript_packages = BTreeSet::new();
+    let mut packages_without_tsconfig = BTreeSet::new();
+    let mut missing = BTreeSet::new();
+    let mut dependency_graph = DependencyGraph::default();
+    let mut requested_tsconfig_files = HashSet::new();
+    for requested_tsconfig_file in tsconfig_files {
+        // Callers gluing lists together may request the same tsconfig more
+        // than once, skip the duplicates before touching the disk
+        if !requested_tsconfig_files.insert(normalize(requested_tsconfig_file.as_ref())) {
+            continue;
+        }
+        let package_directory = options.package_directory_of(requested_tsconfig_file.as_ref());
+        let package_manifest_file =
+            PackageManifestFile(monorepo_root.join(&package_directory).join("package.json"));
+        let package_manifest: PackageManifest = package_manifest_file.try_into()?;
+        let root_package_manifest;
+        let package_manifest = match package_manifests_by_package_name.get(&package_manifest.name) {
+            Some(package_manifest) => package_manifest,
+            // A single package may live in the monorepo root, using
+            // workspaces only for tooling
+            None if package_directory.as_os_str().is_empty() => {
+                root_package_manifest =
+                    MonorepoPackageManifest::from_directory(monorepo_root, Path::new(""))
+                        .map_err(FromTypescriptConfigFileError::RootPackageManifest)?;
+                &root_package_manifest
+            }
+            None => {
+                return Err(FromTypescriptConfigFileError::PackageNotInMonorepo(
+                    requested_tsconfig_file.as_ref().to_owned(),
+                ))
+            }
+        };
+
+        // Honor the requested tsconfig for the target package, which need
+        // not be named tsconfig.json
+        let target_package = TypescriptPackage::new(
+            monorepo_root,
+            package_manifest.contents.name.clone(),
+            requested_tsconfig_file.as_ref().into(),
+            &package_directory,
+        );
+
+        // Enumerate internal dependencies (exclusive)
+        let internal_dependencies: Vec<&MonorepoPackageManifest> = match options.dependency_depth {
+            DependencyDepth::Direct => package_manifest
+                .internal_dependencies_iter(package_manifests_by_package_name)
+                .collect(),
+            DependencyDepth::Transitive => package_manifest
+                .transitive_internal_dependency_package_names_exclusive(
+                    package_manifests_by_package_name,
+                )
+                .collect(),
+        };
+        // Only the packages whose dependencies are followed can miss any
+        let followed_package_manifests = match options.dependency_depth {
+            DependencyDepth::Direct => &[][..],
+            DependencyDepth::Transitive => &internal_dependencies[..],
+        };
+        dependency_graph
+            .requested_packages
+            .insert(package_manifest.contents.name.clone());
+        for followed_package_manifest in
+            iter::once(package_manifest).chain(followed_package_manifests.iter().copied())
+        {
+            missing.extend(missing_internal_dependencies(
+                followed_package_manifest,
+                package_manifests_by_package_name,
+            ));
+            dependency_graph
+                .internal_dependencies
+                .entry(followed_package_manifest.contents.name.clone())
+                .or_default()
+                .extend(
+                    followed_package_manifest
+                        .internal_dependencies_iter(package_manifests_by_package_name)
+                        .map(|dependency| dependency.contents.name.clone()),
+                );
+        }
+        let internal_dependencies = internal_dependencies.into_iter().filter_map(
+            |package_manifest| -> Option<Result<_, PackageInMonorepoRootError>> {
+                let package_manifest_file = PackageManifestFile::from(package_manifest.path());
+                let tsconfig_file: TypescriptConfigFile = match package_manifest_file.try_into() {
+                    Ok(tsconfig_file) => tsconfig_file,
+                    Err(err) => return Some(Err(err)),
+                };
+                if !monorepo_root.join(tsconfig_file.as_path()).is_file() {
+                    packages_without_tsconfig.insert(package_manifest.contents.name.clone());
+                    return None;
+                }
+                Some(Ok(TypescriptPackage::beside_package_manifest(
+                    monorepo_root,
+                    package_manifest.contents.name.clone(),
+                    tsconfig_file,
+                )))
+            },
+        );
+
+        for typescript_package in internal_dependencies
+            // Make this list inclusive of the target package
+            .chain(iter::once(Ok(target_package)))
+        {
+            typescript_packages.insert(typescript_package?);
+        }
+    }
+
+    // A package may be skipped as a dependency yet requested directly
+    // through a tsconfig not named tsconfig.json
+    for typescript_package in &typescript_packages {
+        packages_without_tsconfig.remove(&typescript_package.scoped_package_name);
     }
+    Ok(ResolvedPackages {
+        typescript_packages,
+        packages_without_tsconfig,
+        missing_internal_dependencies: missing,
+        dependency_graph,
+    })
 }
 
-impl TryFrom<&TypescriptConfigFile> for PackageManifestFile {
-    type Error = PackageInMonorepoRootError;
+/// Group each package's included files by scoped package name.
+///
+/// A package enumerated through more than one tsconfig (for example, both
+/// its build and test configurations) reports the sorted union of the files
+/// included by each. When `preserve_compile_order` is set, the union instead
+/// keeps the order in which files were first reported.
+pub(crate) fn group_by_package_name<I>(
+    included_files: I,
+    preserve_compile_order: bool,
+) -> HashMap<String, Vec<PathBuf>>
+where
+    I: IntoIterator<Item = (TypescriptPackage, Vec<PathBuf>)>,
+{
+    let mut grouped: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    for (typescript_package, mut files) in included_files {
+        grouped
+            .entry(typescript_package.scoped_package_name)
+            .and_modify(|existing| {
+                if preserve_compile_order {
+                    let seen: HashSet<PathBuf> = existing.iter().cloned().collect();
+                    existing.extend(files.drain(..).filter(|file| !seen.contains(file)));
+                } else {
+                    existing.append(&mut files);
+                    existing.sort_unstable();
+                    existing.dedup();
+                }
+            })
+            .or_insert(files);
+    }
+    grouped
+}
 
-    fn try_from(value: &TypescriptConfigFile) -> Result<Self, Self::Error> {
-        Self::try_from(value.to_owned())
+/// Count each package's included files by scoped package name, without
+/// holding every path in memory.
+///
+/// `visit` streams the files included by one package to the given callback.
+/// Only a package enumerated through more than one tsconfig needs its paths
+/// retained, so that a file included by several tsconfigs is counted once.
+pub(crate) fn count_by_package_name<F, E>(
+    typescript_packages: BTreeSet<TypescriptPackage>,
+    visit: F,
+) -> Result<HashMap<String, usize>, E>
+where
+    F: Fn(&TypescriptPackage, &mut dyn FnMut(PathBuf)) -> Result<(), E> + Sync,
+    E: Send,
+{
+    let mut typescript_packages_by_package_name: BTreeMap<String, Vec<TypescriptPackage>> =
+        BTreeMap::new();
+    for typescript_package in typescript_packages {
+        typescript_packages_by_package_name
+            .entry(typescript_package.scoped_package_name.clone())
+            .or_default()
+            .push(typescript_package);
+    }
+
+    typescript_packages_by_package_name
+        .into_par_iter()
+        .map(|(package_name, typescript_packages)| {
+            let count = match typescript_packages.as_slice() {
+                [typescript_package] => {
+                    let mut count = 0;
+                    visit(typescript_package, &mut |_| count += 1)?;
+                    count
+                }
+                _ => {
+                    let mut included_files = HashSet::new();
+                    for typescript_package in &typescript_packages {
+                        visit(typescript_package, &mut |file| {
+                            included_files.insert(file);
+                        })?;
+                    }
+                    included_files.len()
+                }
+            };
+            Ok((package_name, count))
+        })
+        .collect()
+}
+
+/// Group each package's included files by the relative path from the
+/// monorepo root to the tsconfig file that includes them.
+pub(crate) fn group_by_tsconfig<I>(included_files: I) -> HashMap<PathBuf, Vec<PathBuf>>
+where
+    I: IntoIterator<Item = (TypescriptPackage, Vec<PathBuf>)>,
+{
+    included_files
+        .into_iter()
+        .map(|(typescript_package, files)| (typescript_package.tsconfig_file.0, files))
+        .collect()
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub(crate) struct PackageManifestFile(PathBuf);
+
+impl From<PathBuf> for PackageManifestFile {
+    fn from(path: PathBuf) -> Self {
+        Self(path)
     }
 }
 
+#[derive(Debug)]
+pub(crate) struct PackageInMonorepoRootError(pub PathBuf);
+
 // This gives us a way to look up the typescript-tools PackageManifest
-// from the path to a package's tsconfig.json file, but it does incur
+// from the path to a package's package.json file, but it does incur
 // a runtime penalty of reading this information from disk again.
 //
 // It's a definite hack, but it unblocks today.
-#[derive(Debug, Deserialize)]
+#[derive(Debug)]
 pub(crate) struct PackageManifest {
     pub name: String,
 }
 
+// `name` is optional in npm for packages that are never published, so we
+// validate it ourselves to report which manifest is missing one.
+#[derive(Debug, Deserialize)]
+struct PackageManifestContents {
+    #[serde(default)]
+    name: Option<serde_json::Value>,
+}
+
 impl TryFrom<PackageManifestFile> for PackageManifest {
-    type Error = FromFileError;
+    type Error = FromTypescriptConfigFileError;
 
     fn try_from(manifest_file: PackageManifestFile) -> Result<Self, Self::Error> {
-        read_json_from_file(manifest_file.0)
+        let contents: PackageManifestContents = read_json_from_file(&manifest_file.0)?;
+        match contents.name {
+            Some(serde_json::Value::String(name)) => Ok(Self { name }),
+            _ => Err(FromTypescriptConfigFileError::MissingPackageName(
+                manifest_file.0,
+            )),
+        }
     }
 }
 
@@ -64,6 +493,11 @@ impl TryFrom<PackageManifestFile> for PackageManifest {
 pub(crate) enum FromTypescriptConfigFileError {
     PackageInMonorepoRoot(PathBuf),
     FromFile(FromFileError),
+    MissingPackageName(PathBuf),
+    /// The tsconfig file, as a relative path from the monorepo root, whose
+    /// package directory holds no package listed in the monorepo manifest.
+    PackageNotInMonorepo(PathBuf),
+    RootPackageManifest(typescript_tools::io::FromFileError),
 }
 
 impl From<PackageInMonorepoRootError> for FromTypescriptConfigFileError {
@@ -78,24 +512,6 @@ impl From<FromFileError> for FromTypescriptConfigFileError {
     }
 }
 
-impl TryFrom<TypescriptConfigFile> for PackageManifest {
-    type Error = FromTypescriptConfigFileError;
-
-    fn try_from(tsconfig_file: TypescriptConfigFile) -> Result<Self, Self::Error> {
-        let package_manifest_file: PackageManifestFile = tsconfig_file.try_into()?;
-        let package_manifest: Self = package_manifest_file.try_into()?;
-        Ok(package_manifest)
-    }
-}
-
-impl TryFrom<&TypescriptConfigFile> for PackageManifest {
-    type Error = FromTypescriptConfigFileError;
-
-    fn try_from(value: &TypescriptConfigFile) -> Result<Self, Self::Error> {
-        Self::try_from(value.to_owned())
-    }
-}
-
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub(crate) struct TypescriptConfigFile(PathBuf);
 
@@ -112,7 +528,7 @@ impl TypescriptConfigFile {
         tsconfig_path
             .parent()
             .map(ToOwned::to_owned)
-            .ok_or_else(|| PackageInMonorepoRootError(tsconfig_path))
+            .ok_or(PackageInMonorepoRootError(tsconfig_path))
     }
 }
 
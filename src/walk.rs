@@ -0,0 +1,139 @@
+//! Directory-walking backends matching `include` globs.
+//!
+//! Both backends walk every file beneath a package directory, including
+//! hidden files and files ignored by version control, and report the files
+//! matching any include glob. The `globwalk` feature, enabled by default,
+//! selects the [globwalk] backend; without it, the [ignore] backend is used,
+//! which avoids globwalk's dependency tree.
+//!
+//! [globwalk]: https://docs.rs/globwalk
+//! [ignore]: https://docs.rs/ignore
+
+use std::path::{Path, PathBuf};
+
+use crate::estimate::WalkError;
+
+/// Walks a package directory for the files matching its include globs.
+pub(crate) trait IncludeWalker {
+    /// Walk the files beneath `package_directory` matching any of
+    /// `include_patterns`, reported as `package_directory` joined with the
+    /// file's relative path.
+    ///
+    /// Patterns are resolved against `package_directory` with gitignore
+    /// semantics, except that a lone `*` only matches the files directly
+    /// inside `package_directory`.
+    ///
+    /// When `max_depth` is set, the walk does not descend more than that many
+    /// directories below `package_directory`, where the files directly inside
+    /// it are at depth 1.
+    fn walk(
+        package_directory: PathBuf,
+        include_patterns: &[String],
+        max_depth: Option<usize>,
+    ) -> Box<dyn Iterator<Item = Result<PathBuf, WalkError>>>;
+}
+
+/// Walker backed by the globwalk crate.
+#[cfg(feature = "globwalk")]
+#[derive(Debug)]
+pub(crate) struct Globwalk;
+
+#[cfg(feature = "globwalk")]
+impl IncludeWalker for Globwalk {
+    fn walk(
+        package_directory: PathBuf,
+        include_patterns: &[String],
+        max_depth: Option<usize>,
+    ) -> Box<dyn Iterator<Item = Result<PathBuf, WalkError>>> {
+        use globwalk::{FileType, GlobWalkerBuilder};
+
+        // globwalk's matcher drops a leading `./` from the base directory but
+        // its walker does not, so walk from the stripped directory instead
+        let (base_directory, walked_directory) = match package_directory.strip_prefix(".") {
+            Ok(stripped) if !stripped.as_os_str().is_empty() => {
+                (Some(PathBuf::from(".")), stripped.to_owned())
+            }
+            _ => (None, package_directory),
+        };
+        let mut builder = GlobWalkerBuilder::from_patterns(walked_directory, include_patterns)
+            .file_type(FileType::FILE)
+            .min_depth(0);
+        if let Some(max_depth) = max_depth {
+            builder = builder.max_depth(max_depth);
+        }
+        let walker = builder
+            .build()
+            .expect("should be able to create glob walker")
+            .map(move |maybe_dir_entry| {
+                let path = maybe_dir_entry?.into_path();
+                Ok(match &base_directory {
+                    Some(base_directory) => base_directory.join(path),
+                    None => path,
+                })
+            });
+        Box::new(walker)
+    }
+}
+
+/// Walker backed by the ignore crate.
+// Still compiled alongside globwalk to keep both backends building
+#[cfg_attr(feature = "globwalk", allow(dead_code))]
+#[derive(Debug)]
+pub(crate) struct IgnoreWalk;
+
+impl IncludeWalker for IgnoreWalk {
+    fn walk(
+        package_directory: PathBuf,
+        include_patterns: &[String],
+        max_depth: Option<usize>,
+    ) -> Box<dyn Iterator<Item = Result<PathBuf, WalkError>>> {
+        let overrides = include_matcher(&package_directory, include_patterns.iter().cloned());
+        let walker = ignore::WalkBuilder::new(package_directory)
+            .standard_filters(false)
+            .overrides(overrides)
+            .max_depth(max_depth)
+            .build()
+            .filter_map(|maybe_dir_entry| match maybe_dir_entry {
+                Ok(dir_entry) => dir_entry
+                    .file_type()
+                    .is_some_and(|file_type| file_type.is_file())
+                    .then(|| Ok(dir_entry.into_path())),
+                Err(err) => Some(Err(err.into())),
+            });
+        Box::new(walker)
+    }
+}
+
+/// The walker selected by this crate's features.
+#[cfg(feature = "globwalk")]
+pub(crate) type Walker = Globwalk;
+
+/// The walker selected by this crate's features.
+#[cfg(not(feature = "globwalk"))]
+pub(crate) type Walker = IgnoreWalk;
+
+/// Build a matcher for `patterns` resolved against `package_directory`,
+/// mirroring the matcher used by GlobWalkerBuilder::from_patterns.
+pub(crate) fn include_matcher<I>(
+    package_directory: &Path,
+    patterns: I,
+) -> ignore::overrides::Override
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut builder = ignore::overrides::OverrideBuilder::new(package_directory);
+    for pattern in patterns {
+        // A lone asterisk would otherwise match files in subdirectories too
+        let pattern = if pattern == "*" {
+            String::from("/*")
+        } else {
+            pattern
+        };
+        builder
+            .add(&pattern)
+            .expect("should be able to create glob matcher");
+    }
+    builder
+        .build()
+        .expect("should be able to create glob matcher")
+}
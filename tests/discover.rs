@@ -0,0 +1,90 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tsconfig_includes::discover::{discover_tsconfigs, packages_extending};
+
+mod support;
+
+use support::{copy_directory, generate_monorepo, scoped_package_name, PackageSpec};
+
+#[test]
+fn discover_tsconfigs_happy_path() {
+    let actual = discover_tsconfigs("test-data/happy-path").unwrap();
+    assert_eq!(
+        actual,
+        [
+            (
+                String::from("@typescript-tools/bar"),
+                PathBuf::from("packages/bar/tsconfig.json")
+            ),
+            (
+                String::from("@typescript-tools/foo"),
+                PathBuf::from("packages/foo/tsconfig.json")
+            ),
+        ]
+    );
+}
+
+#[test]
+fn discover_tsconfigs_skips_packages_without_tsconfig() {
+    let monorepo = tempfile::tempdir().unwrap();
+    copy_directory(Path::new("test-data/happy-path"), monorepo.path());
+    fs::remove_file(monorepo.path().join("packages/foo/tsconfig.json")).unwrap();
+
+    let actual = discover_tsconfigs(monorepo.path()).unwrap();
+    assert_eq!(
+        actual,
+        [(
+            String::from("@typescript-tools/bar"),
+            PathBuf::from("packages/bar/tsconfig.json")
+        )]
+    );
+}
+
+#[test]
+fn packages_extending_base_tsconfig() {
+    let monorepo =
+        generate_monorepo(["a", "b", "c", "d"].map(|name| PackageSpec::new(name, vec![])));
+    let write = |path: &str, contents: &str| {
+        let path = monorepo.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    };
+    write("tsconfig.base.json", "{}");
+    write(
+        "configs/strict.json",
+        r#"{ "extends": "../tsconfig.base.json" }"#,
+    );
+    // Cycles back on itself without ever reaching the base
+    write("configs/loose.json", r#"{ "extends": "./loose" }"#);
+    write(
+        "packages/a/tsconfig.json",
+        r#"{ "extends": "../../tsconfig.base.json" }"#,
+    );
+    write(
+        "packages/b/tsconfig.json",
+        r#"{ "extends": ["../../configs/loose.json", "../../configs/strict"] }"#,
+    );
+    write(
+        "packages/c/tsconfig.json",
+        r#"{ "extends": "../../configs/loose.json" }"#,
+    );
+
+    assert_eq!(
+        packages_extending(monorepo.path(), "tsconfig.base.json").unwrap(),
+        ["a", "b"].map(scoped_package_name)
+    );
+    assert_eq!(
+        packages_extending(monorepo.path(), "configs/loose.json").unwrap(),
+        ["b", "c"].map(scoped_package_name)
+    );
+
+    write(
+        "packages/d/tsconfig.json",
+        r#"{ "extends": "@missing/tsconfig" }"#,
+    );
+    let error = packages_extending(monorepo.path(), "tsconfig.base.json")
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("@missing/tsconfig"), "{}", error);
+}
@@ -1,7 +1,24 @@
 use std::collections::HashMap;
+use std::fs;
 use std::path::{Path, PathBuf};
 
-use tsconfig_includes::estimate::tsconfig_includes_by_package_name;
+use tsconfig_includes::estimate::{
+    self, default_excludes, package_includes_file, tsconfig_include_counts_by_package_name,
+    tsconfig_includes_by_package_name, tsconfig_includes_by_package_name_partial,
+    tsconfig_includes_by_tsconfig, tsconfig_includes_from_str, CompilerOptions,
+};
+use tsconfig_includes::options::{
+    Calculation, DependencyDepth, Digest, EnumerationOptions, PackageOverride, PathStyle,
+};
+use tsconfig_includes::report::render_error_chain;
+use tsconfig_includes::result::{files_by_package, FileKind, Warning};
+
+mod support;
+
+use support::{
+    copy_directory, generate_monorepo, normalize_grouped_separators, scoped_package_name,
+    PackageSpec,
+};
 
 struct PackageIncludes {
     tsconfig_file: String,
@@ -42,7 +59,10 @@ where
                 })
                 .collect();
 
-            assert_eq!(actual, expected);
+            assert_eq!(
+                normalize_grouped_separators(actual),
+                normalize_grouped_separators(expected)
+            );
         }
         // Don't care what went wrong for now
         Err(err) => {
@@ -76,6 +96,43 @@ fn list_grouped_estimate_happy_path_dependencies_bar() {
     );
 }
 
+#[test]
+fn list_grouped_by_tsconfig_estimate_happy_path_dependencies_bar() {
+    let actual = tsconfig_includes_by_tsconfig(
+        &PathBuf::from("test-data/happy-path"),
+        ["packages/bar/tsconfig.json"],
+    )
+    .unwrap();
+    let expected: HashMap<PathBuf, Vec<PathBuf>> = [
+        (
+            "packages/bar/tsconfig.json",
+            vec![
+                "packages/bar/src/bin.ts",
+                "packages/bar/src/index.ts",
+                "packages/bar/src/legacy.js",
+            ],
+        ),
+        (
+            "packages/foo/tsconfig.json",
+            vec![
+                "packages/foo/src/data.json",
+                "packages/foo/src/index.ts",
+                "packages/foo/src/lib.ts",
+            ],
+        ),
+    ]
+    .into_iter()
+    .map(|(tsconfig, files)| {
+        (
+            PathBuf::from(tsconfig),
+            files.into_iter().map(PathBuf::from).collect(),
+        )
+    })
+    .collect();
+
+    assert_eq!(actual, expected);
+}
+
 #[test]
 fn list_grouped_estimate_happy_path_dependencies_foo() {
     check(
@@ -115,3 +172,1508 @@ fn list_grouped_estimate_happy_path_dependencies_foo_and_bar() {
         ],
     );
 }
+
+#[test]
+fn enumeration_options_estimate_matches_estimate_function() {
+    let monorepo_root = PathBuf::from("test-data/happy-path");
+    let tsconfigs = ["packages/bar/tsconfig.json"];
+
+    let actual = EnumerationOptions::default()
+        .calculation(Calculation::Estimate)
+        .enumerate(&monorepo_root, tsconfigs)
+        .unwrap();
+    let expected = tsconfig_includes_by_package_name(&monorepo_root, tsconfigs).unwrap();
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn list_estimate_from_in_memory_tsconfig() {
+    // Differs from the on-disk tsconfig by omitting resolveJsonModule
+    let tsconfig = r#"{ "include": ["src/**/*", "src/**/*.json"] }"#;
+
+    let actual =
+        tsconfig_includes_from_str("test-data/happy-path", "packages/foo", tsconfig).unwrap();
+    let expected: Vec<PathBuf> = ["packages/foo/src/index.ts", "packages/foo/src/lib.ts"]
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn list_estimate_directory_style_includes() {
+    let expected = tsconfig_includes_from_str(
+        "test-data/happy-path",
+        "packages/bar",
+        r#"{ "include": ["src/**/*"] }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        expected,
+        vec![
+            PathBuf::from("packages/bar/src/bin.ts"),
+            PathBuf::from("packages/bar/src/index.ts"),
+        ]
+    );
+
+    for include in ["src/**", "src/", "src"] {
+        let tsconfig = format!(r#"{{ "include": ["{}"] }}"#, include);
+        let actual =
+            tsconfig_includes_from_str("test-data/happy-path", "packages/bar", &tsconfig).unwrap();
+        assert_eq!(actual, expected, "include: {:?}", include);
+    }
+}
+
+#[test]
+fn estimate_reports_package_manifest_without_name() {
+    let monorepo = tempfile::tempdir().unwrap();
+    let root = monorepo.path();
+    fs::write(root.join("lerna.json"), r#"{ "packages": [] }"#).unwrap();
+    fs::create_dir_all(root.join("tools/src")).unwrap();
+    fs::write(root.join("tools/package.json"), r#"{ "private": true }"#).unwrap();
+    fs::write(
+        root.join("tools/tsconfig.json"),
+        r#"{ "include": ["src"] }"#,
+    )
+    .unwrap();
+
+    let err = tsconfig_includes_by_package_name(root, ["tools/tsconfig.json"]).unwrap_err();
+
+    assert!(
+        err.to_string()
+            .contains(&format!("{:?}", root.join("tools/package.json"))),
+        "unexpected error message: {}",
+        err
+    );
+}
+
+#[test]
+fn list_grouped_estimate_relative_to_package_dependencies_bar() {
+    let actual = EnumerationOptions::default()
+        .calculation(Calculation::Estimate)
+        .path_style(PathStyle::RelativeToPackage)
+        .enumerate("test-data/happy-path", ["packages/bar/tsconfig.json"])
+        .unwrap();
+    let expected: HashMap<String, Vec<PathBuf>> = [
+        (
+            "@typescript-tools/bar",
+            vec!["src/bin.ts", "src/index.ts", "src/legacy.js"],
+        ),
+        (
+            "@typescript-tools/foo",
+            vec!["src/data.json", "src/index.ts", "src/lib.ts"],
+        ),
+    ]
+    .into_iter()
+    .map(|(package, files)| {
+        (
+            package.to_owned(),
+            files.into_iter().map(PathBuf::from).collect(),
+        )
+    })
+    .collect();
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn list_grouped_by_tsconfig_estimate_deduplicates_equivalent_paths() {
+    let actual = tsconfig_includes_by_tsconfig(
+        "test-data/happy-path",
+        [
+            "./packages/foo/tsconfig.json",
+            "packages/bar/../foo/tsconfig.json",
+            "packages/bar/tsconfig.json",
+        ],
+    )
+    .unwrap();
+
+    let mut tsconfigs: Vec<&PathBuf> = actual.keys().collect();
+    tsconfigs.sort_unstable();
+    assert_eq!(
+        tsconfigs,
+        [
+            &PathBuf::from("packages/bar/tsconfig.json"),
+            &PathBuf::from("packages/foo/tsconfig.json"),
+        ]
+    );
+}
+
+#[test]
+fn list_estimate_config_dir_template_includes() {
+    let expected = vec![
+        PathBuf::from("packages/bar/src/bin.ts"),
+        PathBuf::from("packages/bar/src/index.ts"),
+    ];
+
+    for include in ["${configDir}/src/**/*", "${configDir}/src"] {
+        let tsconfig = format!(r#"{{ "include": ["{}"] }}"#, include);
+        let actual =
+            tsconfig_includes_from_str("test-data/happy-path", "packages/bar", &tsconfig).unwrap();
+        assert_eq!(actual, expected, "include: {:?}", include);
+    }
+}
+
+#[test]
+fn package_includes_file_estimate() {
+    let included = |file: &str| {
+        package_includes_file("test-data/happy-path", "packages/foo/tsconfig.json", file).unwrap()
+    };
+
+    assert!(included("packages/foo/src/lib.ts"));
+    assert!(included("./packages/foo/src/data.json"));
+    // Excluded by file extension, since foo does not set allowJs
+    assert!(!included("packages/foo/src/legacy.js"));
+    // Outside of foo's include globs
+    assert!(!included("packages/bar/src/index.ts"));
+    // Does not exist on disk
+    assert!(!included("packages/foo/src/missing.ts"));
+}
+
+#[test]
+fn package_includes_file_estimate_honors_default_excludes() {
+    let monorepo = generate_monorepo([PackageSpec::new("a", vec![])]);
+    let package = monorepo.path().join("packages/a");
+    fs::write(
+        package.join("tsconfig.json"),
+        r#"{ "compilerOptions": { "outDir": "dist" }, "include": ["**/*"] }"#,
+    )
+    .unwrap();
+    for file in ["dist/index.d.ts", "node_modules/x/index.ts"] {
+        let path = package.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "export {};\n").unwrap();
+    }
+
+    let enumerated =
+        tsconfig_includes_by_package_name(monorepo.path(), ["packages/a/tsconfig.json"]).unwrap();
+    assert_eq!(
+        normalize_grouped_separators(enumerated)[&scoped_package_name("a")],
+        [PathBuf::from("packages/a/src/index.ts")]
+    );
+
+    let included = |file: &str| {
+        package_includes_file(monorepo.path(), "packages/a/tsconfig.json", file).unwrap()
+    };
+    assert!(included("packages/a/src/index.ts"));
+    assert!(!included("packages/a/dist/index.d.ts"));
+    assert!(!included("packages/a/node_modules/x/index.ts"));
+}
+
+#[test]
+fn list_estimate_follows_reference_directives() {
+    let monorepo = tempfile::tempdir().unwrap();
+    copy_directory(Path::new("test-data/happy-path"), monorepo.path());
+    let foo = monorepo.path().join("packages/foo");
+    fs::create_dir_all(foo.join("types")).unwrap();
+    fs::write(
+        foo.join("types/ambient.d.ts"),
+        "/// <reference path='./globals' />\ndeclare const ambient: string;\n",
+    )
+    .unwrap();
+    fs::write(
+        foo.join("types/globals.d.ts"),
+        "declare const global: string;\n",
+    )
+    .unwrap();
+    fs::write(
+        foo.join("types/ignored.d.ts"),
+        "declare const ignored: string;\n",
+    )
+    .unwrap();
+    fs::write(
+        foo.join("src/index.ts"),
+        [
+            "// Copyright notice",
+            "/// <reference path=\"../types/ambient.d.ts\" />",
+            "export const foo = ambient;",
+            // tsc ignores directives after the first statement
+            "/// <reference path=\"../types/ignored.d.ts\" />",
+        ]
+        .join("\n"),
+    )
+    .unwrap();
+
+    let actual = EnumerationOptions::default()
+        .calculation(Calculation::Estimate)
+        .follow_reference_directives(true)
+        .enumerate(monorepo.path(), ["packages/foo/tsconfig.json"])
+        .unwrap();
+
+    assert_eq!(
+        actual["@typescript-tools/foo"],
+        [
+            PathBuf::from("packages/foo/src/data.json"),
+            PathBuf::from("packages/foo/src/index.ts"),
+            PathBuf::from("packages/foo/src/lib.ts"),
+            PathBuf::from("packages/foo/types/ambient.d.ts"),
+            PathBuf::from("packages/foo/types/globals.d.ts"),
+        ]
+    );
+}
+
+#[test]
+fn list_grouped_estimate_across_monorepos() {
+    let other_monorepo = tempfile::tempdir().unwrap();
+    copy_directory(Path::new("test-data/happy-path"), other_monorepo.path());
+    fs::remove_file(other_monorepo.path().join("packages/foo/src/lib.ts")).unwrap();
+
+    let actual = EnumerationOptions::default()
+        .calculation(Calculation::Estimate)
+        .enumerate_monorepos([
+            ("test-data/happy-path", ["packages/foo/tsconfig.json"]),
+            (
+                other_monorepo.path().to_str().unwrap(),
+                ["packages/foo/tsconfig.json"],
+            ),
+        ])
+        .unwrap();
+
+    assert_eq!(
+        actual[Path::new("test-data/happy-path")]["@typescript-tools/foo"],
+        [
+            PathBuf::from("packages/foo/src/data.json"),
+            PathBuf::from("packages/foo/src/index.ts"),
+            PathBuf::from("packages/foo/src/lib.ts"),
+        ]
+    );
+    assert_eq!(
+        actual[other_monorepo.path()]["@typescript-tools/foo"],
+        [
+            PathBuf::from("packages/foo/src/data.json"),
+            PathBuf::from("packages/foo/src/index.ts"),
+        ]
+    );
+}
+
+#[test]
+fn count_grouped_estimate_happy_path_dependencies_bar() {
+    let actual = tsconfig_include_counts_by_package_name(
+        "test-data/happy-path",
+        ["packages/bar/tsconfig.json"],
+    )
+    .unwrap();
+    let expected = HashMap::from([
+        (String::from("@typescript-tools/bar"), 3),
+        (String::from("@typescript-tools/foo"), 3),
+    ]);
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn list_estimate_extends_published_config_package() {
+    let monorepo = tempfile::tempdir().unwrap();
+    copy_directory(Path::new("test-data/happy-path"), monorepo.path());
+    let base = monorepo.path().join("node_modules/@tsconfig/node18");
+    fs::create_dir_all(&base).unwrap();
+    fs::write(
+        base.join("package.json"),
+        r#"{ "name": "@tsconfig/node18" }"#,
+    )
+    .unwrap();
+    fs::write(
+        base.join("tsconfig.json"),
+        r#"{ "compilerOptions": { "allowJs": true } }"#,
+    )
+    .unwrap();
+
+    for extends in ["@tsconfig/node18/tsconfig.json", "@tsconfig/node18"] {
+        fs::write(
+            monorepo.path().join("packages/foo/tsconfig.json"),
+            format!(r#"{{ "extends": "{}", "include": ["src"] }}"#, extends),
+        )
+        .unwrap();
+
+        let actual = EnumerationOptions::default()
+            .calculation(Calculation::Estimate)
+            .enumerate(monorepo.path(), ["packages/foo/tsconfig.json"])
+            .unwrap();
+        assert_eq!(
+            actual["@typescript-tools/foo"],
+            [
+                PathBuf::from("packages/foo/src/index.ts"),
+                PathBuf::from("packages/foo/src/legacy.js"),
+                PathBuf::from("packages/foo/src/lib.ts"),
+            ],
+            "extends: {:?}",
+            extends
+        );
+    }
+}
+
+#[test]
+fn list_estimate_inherits_include_from_relative_extends() {
+    let monorepo = tempfile::tempdir().unwrap();
+    copy_directory(Path::new("test-data/happy-path"), monorepo.path());
+    fs::write(
+        monorepo.path().join("packages/tsconfig.base.json"),
+        r#"{ "include": ["${configDir}/src"], "compilerOptions": { "allowJs": true } }"#,
+    )
+    .unwrap();
+    fs::write(
+        monorepo.path().join("packages/foo/tsconfig.json"),
+        r#"{ "extends": "../tsconfig.base", "compilerOptions": { "allowJs": false } }"#,
+    )
+    .unwrap();
+
+    let actual = EnumerationOptions::default()
+        .calculation(Calculation::Estimate)
+        .enumerate(monorepo.path(), ["packages/foo/tsconfig.json"])
+        .unwrap();
+    assert_eq!(
+        actual["@typescript-tools/foo"],
+        [
+            PathBuf::from("packages/foo/src/index.ts"),
+            PathBuf::from("packages/foo/src/lib.ts"),
+        ]
+    );
+}
+
+#[test]
+fn estimate_reports_unresolvable_extends() {
+    let monorepo = tempfile::tempdir().unwrap();
+    copy_directory(Path::new("test-data/happy-path"), monorepo.path());
+    fs::write(
+        monorepo.path().join("packages/foo/tsconfig.json"),
+        r#"{ "extends": "@tsconfig/missing", "include": ["src"] }"#,
+    )
+    .unwrap();
+
+    let error = tsconfig_includes_by_package_name(monorepo.path(), ["packages/foo/tsconfig.json"])
+        .unwrap_err();
+    let source = std::error::Error::source(&error).unwrap().to_string();
+    assert!(source.contains("@tsconfig/missing"), "{}", source);
+}
+
+#[test]
+fn list_estimate_without_include_defaults_to_every_file() {
+    let monorepo = tempfile::tempdir().unwrap();
+    copy_directory(Path::new("test-data/happy-path"), monorepo.path());
+    fs::write(monorepo.path().join("packages/foo/tsconfig.json"), "{}").unwrap();
+
+    let actual = EnumerationOptions::default()
+        .calculation(Calculation::Estimate)
+        .enumerate_detailed(monorepo.path(), ["packages/foo/tsconfig.json"])
+        .unwrap();
+    assert_eq!(
+        actual.included_files["@typescript-tools/foo"],
+        [
+            PathBuf::from("packages/foo/src/index.ts"),
+            PathBuf::from("packages/foo/src/lib.ts"),
+        ]
+    );
+    assert!(actual.warnings.is_empty());
+}
+
+#[test]
+fn list_estimate_warns_about_empty_project() {
+    let monorepo = tempfile::tempdir().unwrap();
+    copy_directory(Path::new("test-data/happy-path"), monorepo.path());
+    fs::remove_dir_all(monorepo.path().join("packages/foo/src")).unwrap();
+    fs::write(monorepo.path().join("packages/foo/tsconfig.json"), "{}").unwrap();
+
+    let actual = EnumerationOptions::default()
+        .calculation(Calculation::Estimate)
+        .enumerate_detailed(monorepo.path(), ["packages/foo/tsconfig.json"])
+        .unwrap();
+    assert!(actual.included_files["@typescript-tools/foo"].is_empty());
+    assert_eq!(
+        actual.warnings,
+        [Warning::EmptyProject {
+            package: String::from("@typescript-tools/foo")
+        }]
+    );
+}
+
+#[test]
+fn list_estimate_follows_imports_through_path_aliases() {
+    let monorepo = tempfile::tempdir().unwrap();
+    copy_directory(Path::new("test-data/happy-path"), monorepo.path());
+    let foo = monorepo.path().join("packages/foo");
+    fs::write(
+        foo.join("tsconfig.json"),
+        r#"{
+            "include": ["src/index.ts"],
+            "compilerOptions": {
+                "baseUrl": ".",
+                "paths": { "@app/*": ["missing/*", "shared/*"] }
+            }
+        }"#,
+    )
+    .unwrap();
+    fs::write(
+        foo.join("src/index.ts"),
+        [
+            "import { lib } from './lib';",
+            "import { util } from \"@app/util\";",
+            "// import './commented';",
+            "export * from 'lodash';",
+        ]
+        .join("\n"),
+    )
+    .unwrap();
+    fs::write(foo.join("src/commented.ts"), "").unwrap();
+    fs::create_dir_all(foo.join("shared")).unwrap();
+    fs::write(foo.join("shared/util.ts"), "export const util = 1;\n").unwrap();
+
+    let options = EnumerationOptions::default()
+        .calculation(Calculation::Estimate)
+        .follow_imports(true);
+
+    let actual = options
+        .clone()
+        .enumerate(monorepo.path(), ["packages/foo/tsconfig.json"])
+        .unwrap();
+    assert_eq!(
+        actual["@typescript-tools/foo"],
+        [
+            PathBuf::from("packages/foo/src/index.ts"),
+            PathBuf::from("packages/foo/src/lib.ts"),
+        ]
+    );
+
+    let actual = options
+        .resolve_path_aliases(true)
+        .enumerate(monorepo.path(), ["packages/foo/tsconfig.json"])
+        .unwrap();
+    assert_eq!(
+        actual["@typescript-tools/foo"],
+        [
+            PathBuf::from("packages/foo/shared/util.ts"),
+            PathBuf::from("packages/foo/src/index.ts"),
+            PathBuf::from("packages/foo/src/lib.ts"),
+        ]
+    );
+}
+
+#[test]
+fn list_estimate_follows_re_export_chains() {
+    let monorepo = generate_monorepo([PackageSpec {
+        files: vec!["a/index.ts", "a/b.ts", "c.ts", "unused.ts"],
+        ..PackageSpec::new("app", vec![])
+    }]);
+    let src = monorepo.path().join("packages/app/src");
+    fs::write(
+        src.join("../tsconfig.json"),
+        r#"{ "include": ["src/index.ts"] }"#,
+    )
+    .unwrap();
+    fs::write(src.join("index.ts"), "export * from './a';\n").unwrap();
+    fs::write(src.join("a/index.ts"), "export * from './b';\n").unwrap();
+    // Cycles back into the barrel
+    fs::write(
+        src.join("a/b.ts"),
+        "export * from '../c';\nexport * from '.';\nexport * from 'lodash';\n",
+    )
+    .unwrap();
+
+    let actual = EnumerationOptions::default()
+        .calculation(Calculation::Estimate)
+        .follow_imports(true)
+        .enumerate(monorepo.path(), ["packages/app/tsconfig.json"])
+        .unwrap();
+    assert_eq!(
+        normalize_grouped_separators(actual)[&scoped_package_name("app")],
+        [
+            PathBuf::from("packages/app/src/a/b.ts"),
+            PathBuf::from("packages/app/src/a/index.ts"),
+            PathBuf::from("packages/app/src/c.ts"),
+            PathBuf::from("packages/app/src/index.ts"),
+        ]
+    );
+}
+
+#[test]
+fn list_estimate_tree_nests_internal_dependencies() {
+    let monorepo = generate_monorepo([
+        PackageSpec::new("d", vec![]),
+        PackageSpec::new("b", vec!["d"]),
+        PackageSpec::new("c", vec!["d"]),
+        PackageSpec::new("a", vec!["b", "c"]),
+        PackageSpec::new("e", vec![]),
+    ]);
+
+    let actual = EnumerationOptions::default()
+        .calculation(Calculation::Estimate)
+        .enumerate_tree(
+            monorepo.path(),
+            ["packages/a/tsconfig.json", "packages/e/tsconfig.json"],
+        )
+        .unwrap();
+
+    let mut requested: Vec<&String> = actual.keys().collect();
+    requested.sort_unstable();
+    assert_eq!(
+        requested,
+        [&scoped_package_name("a"), &scoped_package_name("e")]
+    );
+
+    let a = &actual[&scoped_package_name("a")];
+    assert_eq!(a.files, [PathBuf::from("packages/a/src/index.ts")]);
+    assert_eq!(
+        a.dependencies.keys().collect::<Vec<_>>(),
+        [&scoped_package_name("b"), &scoped_package_name("c")]
+    );
+    // d is reached through both b and c, and appears beneath each
+    for dependent in ["b", "c"] {
+        let dependent = &a.dependencies[&scoped_package_name(dependent)];
+        let d = &dependent.dependencies[&scoped_package_name("d")];
+        assert_eq!(d.files, [PathBuf::from("packages/d/src/index.ts")]);
+        assert!(d.dependencies.is_empty());
+    }
+
+    let e = &actual[&scoped_package_name("e")];
+    assert_eq!(e.files, [PathBuf::from("packages/e/src/index.ts")]);
+    assert!(e.dependencies.is_empty());
+}
+
+#[test]
+fn list_estimate_resolves_package_directory_for_mirrored_build_configs() {
+    let monorepo = generate_monorepo([
+        PackageSpec::new("lib", vec![]),
+        PackageSpec::new("app", vec!["lib"]),
+    ]);
+    let build_config = monorepo.path().join("build-configs/app/tsconfig.json");
+    fs::create_dir_all(build_config.parent().unwrap()).unwrap();
+    fs::write(&build_config, r#"{ "include": ["src/**/*"] }"#).unwrap();
+
+    let actual = EnumerationOptions::default()
+        .calculation(Calculation::Estimate)
+        .path_style(PathStyle::RelativeToPackage)
+        .include_base("build-configs/app/tsconfig.json", "packages/app")
+        .resolve_package_directory(|tsconfig_file| {
+            Path::new("packages").join(tsconfig_file.parent().unwrap().file_name().unwrap())
+        })
+        .enumerate(monorepo.path(), ["build-configs/app/tsconfig.json"])
+        .unwrap();
+    let expected: HashMap<String, Vec<PathBuf>> = [
+        (
+            scoped_package_name("app"),
+            vec![PathBuf::from("src/index.ts")],
+        ),
+        (
+            scoped_package_name("lib"),
+            vec![PathBuf::from("src/index.ts")],
+        ),
+    ]
+    .into();
+    assert_eq!(normalize_grouped_separators(actual), expected);
+}
+
+#[test]
+fn list_estimate_applies_package_overrides() {
+    let monorepo = generate_monorepo([
+        PackageSpec {
+            files: vec!["phantom.ts"],
+            ..PackageSpec::new("lib", vec![])
+        },
+        PackageSpec::new("app", vec!["lib"]),
+    ]);
+
+    let actual = EnumerationOptions::default()
+        .calculation(Calculation::Estimate)
+        .package_overrides([(
+            scoped_package_name("lib"),
+            PackageOverride::default()
+                .add_files(["packages/lib/generated/schema.ts"])
+                .remove_files(["packages/lib/src/phantom.ts", "packages/lib/src/renamed.ts"]),
+        )])
+        .enumerate_detailed(monorepo.path(), ["packages/app/tsconfig.json"])
+        .unwrap();
+    assert_eq!(
+        normalize_grouped_separators(actual.included_files)[&scoped_package_name("lib")],
+        [
+            PathBuf::from("packages/lib/generated/schema.ts"),
+            PathBuf::from("packages/lib/src/index.ts"),
+        ]
+    );
+    assert_eq!(
+        actual.warnings,
+        [Warning::UnmatchedOverride {
+            package: scoped_package_name("lib"),
+            file: PathBuf::from("packages/lib/src/renamed.ts"),
+        }]
+    );
+}
+
+#[test]
+fn list_estimate_partitions_files_by_kind() {
+    let monorepo = generate_monorepo([PackageSpec {
+        files: vec![
+            "component.tsx",
+            "types.d.ts",
+            "styles.d.css.ts",
+            "legacy.js",
+            "data.json",
+        ],
+        ..PackageSpec::new("app", vec![])
+    }]);
+    fs::write(
+        monorepo.path().join("packages/app/tsconfig.json"),
+        r#"{
+            "compilerOptions": { "allowJs": true, "allowArbitraryExtensions": true, "resolveJsonModule": true },
+            "include": ["src/**/*", "src/**/*.json"]
+        }"#,
+    )
+    .unwrap();
+
+    let actual = EnumerationOptions::default()
+        .calculation(Calculation::Estimate)
+        .path_style(PathStyle::RelativeToPackage)
+        .enumerate_partitioned(monorepo.path(), ["packages/app/tsconfig.json"])
+        .unwrap();
+    let app = &actual[&scoped_package_name("app")];
+    assert_eq!(
+        app.source,
+        [
+            PathBuf::from("src/component.tsx"),
+            PathBuf::from("src/index.ts"),
+        ]
+    );
+    assert_eq!(
+        app.declarations,
+        [
+            PathBuf::from("src/styles.d.css.ts"),
+            PathBuf::from("src/types.d.ts"),
+        ]
+    );
+    assert_eq!(app.javascript, [PathBuf::from("src/legacy.js")]);
+    assert_eq!(
+        app.of_kind(FileKind::Json),
+        [PathBuf::from("src/data.json")]
+    );
+    assert!(app.other.is_empty());
+    assert_eq!(FileKind::of("src/App.vue"), FileKind::Other);
+}
+
+#[test]
+fn list_grouped_estimate_deep_dependency_chain() {
+    let monorepo = generate_monorepo([
+        PackageSpec::new("a", vec!["b"]),
+        PackageSpec::new("b", vec!["c"]),
+        PackageSpec::new("c", vec!["d", "e"]),
+        PackageSpec::new("d", vec!["e"]),
+        PackageSpec {
+            files: vec!["lib.ts", "nested/util.ts"],
+            ..PackageSpec::new("e", vec![])
+        },
+        PackageSpec::new("unrelated", vec![]),
+    ]);
+
+    let actual =
+        tsconfig_includes_by_package_name(monorepo.path(), ["packages/a/tsconfig.json"]).unwrap();
+
+    let mut package_names: Vec<&String> = actual.keys().collect();
+    package_names.sort_unstable();
+    assert_eq!(
+        package_names,
+        ["a", "b", "c", "d", "e"]
+            .map(scoped_package_name)
+            .iter()
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        actual[&scoped_package_name("e")],
+        [
+            PathBuf::from("packages/e/src/index.ts"),
+            PathBuf::from("packages/e/src/lib.ts"),
+            PathBuf::from("packages/e/src/nested/util.ts"),
+        ]
+    );
+}
+
+#[test]
+fn list_estimate_allow_arbitrary_extensions() {
+    let monorepo = tempfile::tempdir().unwrap();
+    copy_directory(Path::new("test-data/happy-path"), monorepo.path());
+    let foo = monorepo.path().join("packages/foo");
+    fs::write(
+        foo.join("tsconfig.json"),
+        r#"{
+            "include": ["src/*.d.*.ts", "src/index.ts"],
+            "compilerOptions": { "allowArbitraryExtensions": true }
+        }"#,
+    )
+    .unwrap();
+    fs::write(foo.join("src/styles.css"), "").unwrap();
+    fs::write(foo.join("src/styles.d.css.ts"), "export default {};\n").unwrap();
+    fs::create_dir_all(foo.join("generated")).unwrap();
+    fs::write(foo.join("generated/theme.css"), "").unwrap();
+    fs::write(foo.join("generated/theme.d.css.ts"), "export default {};\n").unwrap();
+    fs::write(
+        foo.join("src/index.ts"),
+        "import './styles.css';\nimport '../generated/theme.css';\n",
+    )
+    .unwrap();
+
+    let actual = EnumerationOptions::default()
+        .calculation(Calculation::Estimate)
+        .follow_imports(true)
+        .enumerate(monorepo.path(), ["packages/foo/tsconfig.json"])
+        .unwrap();
+    assert_eq!(
+        actual["@typescript-tools/foo"],
+        [
+            PathBuf::from("packages/foo/generated/theme.d.css.ts"),
+            PathBuf::from("packages/foo/src/index.ts"),
+            PathBuf::from("packages/foo/src/styles.d.css.ts"),
+        ]
+    );
+}
+
+#[test]
+fn list_estimate_reports_tsconfig_per_package() {
+    let actual = EnumerationOptions::default()
+        .calculation(Calculation::Estimate)
+        .enumerate_detailed("test-data/happy-path", ["./packages/bar/tsconfig.json"])
+        .unwrap();
+
+    let expected = HashMap::from([
+        (
+            String::from("@typescript-tools/bar"),
+            vec![PathBuf::from("packages/bar/tsconfig.json")],
+        ),
+        (
+            String::from("@typescript-tools/foo"),
+            vec![PathBuf::from("packages/foo/tsconfig.json")],
+        ),
+    ]);
+    assert_eq!(actual.tsconfig_files, expected);
+}
+
+#[test]
+fn list_estimate_brace_expanded_includes() {
+    let monorepo = tempfile::tempdir().unwrap();
+    let src = monorepo.path().join("packages/foo/src");
+    fs::create_dir_all(&src).unwrap();
+    for file in [
+        "index.ts",
+        "view.tsx",
+        "legacy.js",
+        "legacy.jsx",
+        "styles.css",
+    ] {
+        fs::write(src.join(file), "").unwrap();
+    }
+
+    let tsconfig = r#"{
+        "include": ["src/**/*.{ts,tsx}"],
+        "compilerOptions": { "allowJs": true }
+    }"#;
+    let actual = tsconfig_includes_from_str(monorepo.path(), "packages/foo", tsconfig).unwrap();
+    assert_eq!(
+        actual,
+        [
+            PathBuf::from("packages/foo/src/index.ts"),
+            PathBuf::from("packages/foo/src/view.tsx"),
+        ]
+    );
+}
+
+#[test]
+fn list_estimate_compound_suffix_includes() {
+    let monorepo = tempfile::tempdir().unwrap();
+    let src = monorepo.path().join("packages/foo/src");
+    fs::create_dir_all(&src).unwrap();
+    for file in ["index.ts", "index.test.ts", "types.d.ts"] {
+        fs::write(src.join(file), "").unwrap();
+    }
+
+    let tests_only = tsconfig_includes_from_str(
+        monorepo.path(),
+        "packages/foo",
+        r#"{ "include": ["src/**/*.test.ts"] }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        tests_only,
+        [PathBuf::from("packages/foo/src/index.test.ts")]
+    );
+
+    let every_ts_file = tsconfig_includes_from_str(
+        monorepo.path(),
+        "packages/foo",
+        r#"{ "include": ["src/**/*.ts"] }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        every_ts_file,
+        [
+            PathBuf::from("packages/foo/src/index.test.ts"),
+            PathBuf::from("packages/foo/src/index.ts"),
+            PathBuf::from("packages/foo/src/types.d.ts"),
+        ]
+    );
+}
+
+#[test]
+fn list_estimate_glob_extension_applies_only_to_its_glob() {
+    let monorepo = tempfile::tempdir().unwrap();
+    let foo = monorepo.path().join("packages/foo");
+    fs::create_dir_all(foo.join("src")).unwrap();
+    fs::create_dir_all(foo.join("data")).unwrap();
+    fs::write(foo.join("src/index.ts"), "").unwrap();
+    fs::write(foo.join("src/fixture.json"), "{}").unwrap();
+    fs::write(foo.join("data/strings.json"), "{}").unwrap();
+
+    let tsconfig = r#"{
+        "include": ["src/**/*", "data/*.json"],
+        "compilerOptions": { "resolveJsonModule": true }
+    }"#;
+    let actual = tsconfig_includes_from_str(monorepo.path(), "packages/foo", tsconfig).unwrap();
+    assert_eq!(
+        actual,
+        [
+            PathBuf::from("packages/foo/data/strings.json"),
+            PathBuf::from("packages/foo/src/index.ts"),
+        ]
+    );
+}
+
+#[test]
+fn list_estimate_json_globs_skip_config_files() {
+    let monorepo = tempfile::tempdir().unwrap();
+    let foo = monorepo.path().join("packages/foo");
+    fs::create_dir_all(foo.join("src/config")).unwrap();
+    fs::write(foo.join("src/index.ts"), "").unwrap();
+    fs::write(foo.join("src/strings.json"), "{}").unwrap();
+    fs::write(foo.join("src/package.json"), "{}").unwrap();
+    fs::write(foo.join("src/config/tsconfig.settings.json"), "{}").unwrap();
+
+    let tsconfig = r#"{
+        "include": ["src/**/*", "src/**/*.json"],
+        "compilerOptions": { "resolveJsonModule": true }
+    }"#;
+    let actual = tsconfig_includes_from_str(monorepo.path(), "packages/foo", tsconfig).unwrap();
+    assert_eq!(
+        actual,
+        [
+            PathBuf::from("packages/foo/src/index.ts"),
+            PathBuf::from("packages/foo/src/strings.json"),
+        ]
+    );
+
+    let tsconfig = r#"{
+        "include": ["src/**/*", "src/**/*.json"],
+        "files": ["src/config/tsconfig.settings.json"],
+        "compilerOptions": { "resolveJsonModule": true }
+    }"#;
+    let actual = tsconfig_includes_from_str(monorepo.path(), "packages/foo", tsconfig).unwrap();
+    assert_eq!(
+        actual,
+        [
+            PathBuf::from("packages/foo/src/config/tsconfig.settings.json"),
+            PathBuf::from("packages/foo/src/index.ts"),
+            PathBuf::from("packages/foo/src/strings.json"),
+        ]
+    );
+}
+
+#[test]
+fn list_grouped_estimate_direct_dependencies_only() {
+    let monorepo = generate_monorepo([
+        PackageSpec::new("a", vec!["b", "c"]),
+        PackageSpec::new("b", vec!["d"]),
+        PackageSpec::new("c", vec![]),
+        PackageSpec::new("d", vec![]),
+    ]);
+
+    let actual = EnumerationOptions::default()
+        .calculation(Calculation::Estimate)
+        .dependency_depth(DependencyDepth::Direct)
+        .enumerate(monorepo.path(), ["packages/a/tsconfig.json"])
+        .unwrap();
+
+    let mut package_names: Vec<&String> = actual.keys().collect();
+    package_names.sort_unstable();
+    assert_eq!(
+        package_names,
+        ["a", "b", "c"]
+            .map(scoped_package_name)
+            .iter()
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn list_grouped_estimate_duplicate_tsconfig_files() {
+    let deduplicated = tsconfig_includes_by_package_name(
+        "test-data/happy-path",
+        ["packages/foo/tsconfig.json", "packages/bar/tsconfig.json"],
+    )
+    .unwrap();
+    let duplicated = tsconfig_includes_by_package_name(
+        "test-data/happy-path",
+        [
+            "packages/foo/tsconfig.json",
+            "./packages/foo/tsconfig.json",
+            "packages/bar/tsconfig.json",
+        ],
+    )
+    .unwrap();
+    assert_eq!(duplicated, deduplicated);
+}
+
+#[test]
+fn list_estimate_reports_packages_without_tsconfig() {
+    let monorepo = generate_monorepo([
+        PackageSpec::new("a", vec!["b", "c"]),
+        PackageSpec::new("b", vec![]),
+        PackageSpec::new("c", vec![]),
+    ]);
+    fs::remove_file(monorepo.path().join("packages/b/tsconfig.json")).unwrap();
+
+    let actual = EnumerationOptions::default()
+        .calculation(Calculation::Estimate)
+        .enumerate_detailed(monorepo.path(), ["packages/a/tsconfig.json"])
+        .unwrap();
+
+    let mut package_names: Vec<&String> = actual.included_files.keys().collect();
+    package_names.sort_unstable();
+    assert_eq!(
+        package_names,
+        ["a", "c"]
+            .map(scoped_package_name)
+            .iter()
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(actual.packages_without_tsconfig, [scoped_package_name("b")]);
+}
+
+#[test]
+fn default_excludes_include_out_dir() {
+    let compiler_options: CompilerOptions =
+        serde_json::from_str(r#"{ "outDir": "dist" }"#).unwrap();
+    assert_eq!(
+        default_excludes(&compiler_options),
+        ["node_modules", "bower_components", "jspm_packages", "dist"]
+    );
+    assert_eq!(
+        default_excludes(&CompilerOptions::default()),
+        ["node_modules", "bower_components", "jspm_packages"]
+    );
+}
+
+#[test]
+fn list_estimate_applies_default_excludes() {
+    let monorepo = tempfile::tempdir().unwrap();
+    let foo = monorepo.path().join("packages/foo");
+    for directory in [
+        "src",
+        "dist",
+        "node_modules/left-pad",
+        "src/vendor/node_modules/pad",
+    ] {
+        fs::create_dir_all(foo.join(directory)).unwrap();
+    }
+    for file in [
+        "src/index.ts",
+        "dist/index.d.ts",
+        "node_modules/left-pad/index.d.ts",
+        "src/vendor/node_modules/pad/index.d.ts",
+    ] {
+        fs::write(foo.join(file), "").unwrap();
+    }
+
+    let tsconfig = r#"{ "compilerOptions": { "outDir": "dist" } }"#;
+    let actual = tsconfig_includes_from_str(monorepo.path(), "packages/foo", tsconfig).unwrap();
+    assert_eq!(actual, [PathBuf::from("packages/foo/src/index.ts")]);
+}
+
+#[test]
+fn list_grouped_estimate_partial_result() {
+    let monorepo = generate_monorepo([
+        PackageSpec::new("a", vec!["b", "c"]),
+        PackageSpec::new("b", vec![]),
+        PackageSpec::new("c", vec![]),
+    ]);
+    fs::write(
+        monorepo.path().join("packages/b/tsconfig.json"),
+        "{ not json",
+    )
+    .unwrap();
+
+    assert!(
+        tsconfig_includes_by_package_name(monorepo.path(), ["packages/a/tsconfig.json"]).is_err()
+    );
+
+    let (included_files, errors) =
+        tsconfig_includes_by_package_name_partial(monorepo.path(), ["packages/a/tsconfig.json"])
+            .unwrap();
+    let mut package_names: Vec<&String> = included_files.keys().collect();
+    package_names.sort_unstable();
+    assert_eq!(
+        package_names,
+        ["a", "c"]
+            .map(scoped_package_name)
+            .iter()
+            .collect::<Vec<_>>()
+    );
+    let failed: Vec<&String> = errors.iter().map(|(package, _)| package).collect();
+    assert_eq!(failed, [&scoped_package_name("b")]);
+}
+
+#[test]
+fn list_grouped_estimate_partial_result_with_options() {
+    let monorepo = generate_monorepo([
+        PackageSpec::new("a", vec!["b", "c"]),
+        PackageSpec::new("b", vec![]),
+        PackageSpec::new("c", vec![]),
+    ]);
+    fs::write(
+        monorepo.path().join("packages/b/tsconfig.json"),
+        "{ not json",
+    )
+    .unwrap();
+
+    let (included_files, errors) = EnumerationOptions::default()
+        .dependency_depth(DependencyDepth::Direct)
+        .path_style(PathStyle::RelativeToPackage)
+        .package_overrides([(
+            scoped_package_name("c"),
+            PackageOverride::default().add_files(["src/generated.ts"]),
+        )])
+        .enumerate_partial(monorepo.path(), ["packages/a/tsconfig.json"])
+        .unwrap();
+    let expected: HashMap<String, Vec<PathBuf>> = [
+        (
+            scoped_package_name("a"),
+            vec![PathBuf::from("src/index.ts")],
+        ),
+        (
+            scoped_package_name("c"),
+            vec![
+                PathBuf::from("src/generated.ts"),
+                PathBuf::from("src/index.ts"),
+            ],
+        ),
+    ]
+    .into();
+    assert_eq!(normalize_grouped_separators(included_files), expected);
+    let failed: Vec<&String> = errors.iter().map(|(package, _)| package).collect();
+    assert_eq!(failed, [&scoped_package_name("b")]);
+}
+
+#[test]
+fn list_estimate_digests_package_files() {
+    let monorepo = tempfile::tempdir().unwrap();
+    copy_directory(Path::new("test-data/happy-path"), monorepo.path());
+    let digests = |digest: Digest| {
+        EnumerationOptions::default()
+            .calculation(Calculation::Estimate)
+            .digest(digest)
+            .enumerate_detailed(monorepo.path(), ["packages/foo/tsconfig.json"])
+            .unwrap()
+            .digests
+            .remove("@typescript-tools/foo")
+            .unwrap()
+    };
+
+    let paths = digests(Digest::Paths);
+    let contents = digests(Digest::Contents);
+    assert_eq!(paths.len(), 64);
+    assert_ne!(paths, contents);
+
+    fs::write(
+        monorepo.path().join("packages/foo/src/lib.ts"),
+        "export const edited = true;\n",
+    )
+    .unwrap();
+    assert_eq!(digests(Digest::Paths), paths);
+    assert_ne!(digests(Digest::Contents), contents);
+
+    fs::write(monorepo.path().join("packages/foo/src/new.ts"), "").unwrap();
+    assert_ne!(digests(Digest::Paths), paths);
+
+    let undigested = EnumerationOptions::default()
+        .calculation(Calculation::Estimate)
+        .enumerate_detailed(monorepo.path(), ["packages/foo/tsconfig.json"])
+        .unwrap();
+    assert!(undigested.digests.is_empty());
+}
+
+#[test]
+fn list_estimate_detailed_results_compare_equal() {
+    let enumerate = || {
+        EnumerationOptions::default()
+            .calculation(Calculation::Estimate)
+            .enumerate_detailed("test-data/happy-path", ["packages/bar/tsconfig.json"])
+            .unwrap()
+    };
+    let result = enumerate();
+    assert_eq!(result, enumerate());
+    assert_eq!(result.clone(), result);
+}
+
+#[test]
+fn list_grouped_by_tsconfig_estimate_test_tsconfig_for_target_package() {
+    let monorepo = generate_monorepo([
+        PackageSpec::new("a", vec!["b"]),
+        PackageSpec::new("b", vec![]),
+    ]);
+    for package in ["a", "b"] {
+        let package_directory = monorepo.path().join("packages").join(package);
+        fs::create_dir_all(package_directory.join("test")).unwrap();
+        fs::write(package_directory.join("test/index.test.ts"), "").unwrap();
+        fs::write(
+            package_directory.join("tsconfig.test.json"),
+            r#"{ "extends": "./tsconfig.json", "include": ["test/**/*"] }"#,
+        )
+        .unwrap();
+    }
+
+    let actual =
+        tsconfig_includes_by_tsconfig(monorepo.path(), ["packages/a/tsconfig.test.json"]).unwrap();
+    let expected: HashMap<PathBuf, Vec<PathBuf>> = [
+        (
+            "packages/a/tsconfig.test.json",
+            "packages/a/test/index.test.ts",
+        ),
+        ("packages/b/tsconfig.json", "packages/b/src/index.ts"),
+    ]
+    .into_iter()
+    .map(|(tsconfig, file)| (PathBuf::from(tsconfig), vec![PathBuf::from(file)]))
+    .collect();
+    assert_eq!(
+        normalize_grouped_separators(actual),
+        normalize_grouped_separators(expected)
+    );
+}
+
+#[test]
+fn list_estimate_transforms_paths() {
+    let monorepo = generate_monorepo([PackageSpec {
+        files: vec!["__generated__/schema.ts", "lib.ts"],
+        ..PackageSpec::new("a", vec![])
+    }]);
+
+    let actual = EnumerationOptions::default()
+        .calculation(Calculation::Estimate)
+        .transform_paths(|file| {
+            if file
+                .components()
+                .any(|component| component.as_os_str() == "__generated__")
+            {
+                return None;
+            }
+            Some(Path::new("canonical").join(file))
+        })
+        .enumerate(monorepo.path(), ["packages/a/tsconfig.json"])
+        .unwrap();
+    assert_eq!(
+        actual[&scoped_package_name("a")],
+        [
+            PathBuf::from("canonical/packages/a/src/index.ts"),
+            PathBuf::from("canonical/packages/a/src/lib.ts"),
+        ]
+    );
+}
+
+#[test]
+fn estimate_reports_duplicate_package_names() {
+    let monorepo =
+        generate_monorepo([PackageSpec::new("a", vec![]), PackageSpec::new("b", vec![])]);
+    fs::write(
+        monorepo.path().join("packages/b/package.json"),
+        serde_json::json!({ "name": scoped_package_name("a"), "version": "0.0.0" }).to_string(),
+    )
+    .unwrap();
+
+    let error = tsconfig_includes_by_package_name(monorepo.path(), ["packages/a/tsconfig.json"])
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("packages/a/package.json"), "{}", error);
+    assert!(error.contains("packages/b/package.json"), "{}", error);
+}
+
+#[test]
+fn list_estimate_reports_tsconfig_outside_every_package() {
+    let monorepo = generate_monorepo([PackageSpec::new("a", vec![])]);
+    let stray = monorepo.path().join("stray");
+    fs::create_dir_all(&stray).unwrap();
+    fs::write(
+        stray.join("package.json"),
+        serde_json::json!({ "name": "stray", "version": "0.0.0" }).to_string(),
+    )
+    .unwrap();
+    fs::write(stray.join("tsconfig.json"), r#"{ "include": ["src"] }"#).unwrap();
+
+    let err =
+        tsconfig_includes_by_package_name(monorepo.path(), ["stray/tsconfig.json"]).unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        estimate::ErrorKind::PackageNotInMonorepo { .. }
+    ));
+    assert!(
+        err.to_string().contains("stray/tsconfig.json"),
+        "unexpected error message: {}",
+        err
+    );
+
+    // A resolver may point a listed package's tsconfig elsewhere too
+    let err = EnumerationOptions::default()
+        .calculation(Calculation::Estimate)
+        .resolve_package_directory(|_| PathBuf::from("stray"))
+        .enumerate(monorepo.path(), ["packages/a/tsconfig.json"])
+        .unwrap_err();
+    assert!(
+        render_error_chain(&err).contains("does not belong to a package in the monorepo"),
+        "unexpected error message: {}",
+        render_error_chain(&err)
+    );
+}
+
+#[test]
+fn list_grouped_estimate_package_in_monorepo_root() {
+    let monorepo = tempfile::tempdir().unwrap();
+    let write = |path: &str, contents: String| {
+        let path = monorepo.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    };
+    write(
+        "package.json",
+        serde_json::json!({
+            "name": "root",
+            "version": "0.0.0",
+            "workspaces": ["packages/*"],
+            "dependencies": { "lib": "0.0.0" },
+        })
+        .to_string(),
+    );
+    write("tsconfig.json", r#"{ "include": ["src/**/*"] }"#.to_owned());
+    write("src/index.ts", String::new());
+    write(
+        "packages/lib/package.json",
+        serde_json::json!({ "name": "lib", "version": "0.0.0" }).to_string(),
+    );
+    write(
+        "packages/lib/tsconfig.json",
+        r#"{ "include": ["src/**/*"] }"#.to_owned(),
+    );
+    write("packages/lib/src/index.ts", String::new());
+
+    let actual = tsconfig_includes_by_package_name(monorepo.path(), ["tsconfig.json"]).unwrap();
+    let expected: HashMap<String, Vec<PathBuf>> = [
+        ("root", "src/index.ts"),
+        ("lib", "packages/lib/src/index.ts"),
+    ]
+    .into_iter()
+    .map(|(package, file)| (package.to_owned(), vec![PathBuf::from(file)]))
+    .collect();
+    assert_eq!(
+        normalize_grouped_separators(actual),
+        normalize_grouped_separators(expected)
+    );
+}
+
+#[test]
+fn list_estimate_includes_path_mapped_declarations() {
+    let monorepo = tempfile::tempdir().unwrap();
+    copy_directory(Path::new("test-data/happy-path"), monorepo.path());
+    let foo = monorepo.path().join("packages/foo");
+    fs::write(
+        foo.join("tsconfig.json"),
+        r#"{
+            "include": ["src"],
+            "compilerOptions": {
+                "paths": {
+                    "globals": ["./types/globals.d.ts"],
+                    "missing": ["./types/missing.d.ts"],
+                    "@app/*": ["./types/*"]
+                }
+            }
+        }"#,
+    )
+    .unwrap();
+    fs::create_dir_all(foo.join("types")).unwrap();
+    fs::write(foo.join("types/globals.d.ts"), "").unwrap();
+    fs::write(foo.join("types/other.d.ts"), "").unwrap();
+
+    let options = EnumerationOptions::default().calculation(Calculation::Estimate);
+    let without = options
+        .clone()
+        .enumerate(monorepo.path(), ["packages/foo/tsconfig.json"])
+        .unwrap();
+    assert_eq!(
+        without["@typescript-tools/foo"],
+        [
+            PathBuf::from("packages/foo/src/index.ts"),
+            PathBuf::from("packages/foo/src/lib.ts"),
+        ]
+    );
+
+    let with = options
+        .include_path_mapped_declarations(true)
+        .enumerate(monorepo.path(), ["packages/foo/tsconfig.json"])
+        .unwrap();
+    assert_eq!(
+        with["@typescript-tools/foo"],
+        [
+            PathBuf::from("packages/foo/src/index.ts"),
+            PathBuf::from("packages/foo/src/lib.ts"),
+            PathBuf::from("packages/foo/types/globals.d.ts"),
+        ]
+    );
+}
+
+#[test]
+fn list_estimate_flattens_files_by_package() {
+    let monorepo = generate_monorepo([
+        PackageSpec::new("lib", vec![]),
+        PackageSpec {
+            files: vec!["view.ts"],
+            ..PackageSpec::new("app", vec!["lib"])
+        },
+    ]);
+
+    let actual = EnumerationOptions::default()
+        .calculation(Calculation::Estimate)
+        .enumerate_detailed(monorepo.path(), ["packages/app/tsconfig.json"])
+        .unwrap();
+    let app = scoped_package_name("app");
+    let lib = scoped_package_name("lib");
+    let expected = [
+        (app.as_str(), Path::new("packages/app/src/index.ts")),
+        (app.as_str(), Path::new("packages/app/src/view.ts")),
+        (lib.as_str(), Path::new("packages/lib/src/index.ts")),
+    ];
+    assert_eq!(actual.files().len(), expected.len());
+    assert_eq!(actual.files().collect::<Vec<_>>(), expected);
+    assert_eq!(
+        files_by_package(&actual.included_files).collect::<Vec<_>>(),
+        expected
+    );
+}
+
+#[test]
+fn list_estimate_bounds_walk_depth() {
+    let monorepo = generate_monorepo([PackageSpec {
+        files: vec!["generated/deep/nested.ts"],
+        ..PackageSpec::new("app", vec![])
+    }]);
+
+    let options = EnumerationOptions::default().calculation(Calculation::Estimate);
+    let unbounded = options
+        .clone()
+        .enumerate(monorepo.path(), ["packages/app/tsconfig.json"])
+        .unwrap();
+    assert_eq!(
+        normalize_grouped_separators(unbounded)[&scoped_package_name("app")],
+        [
+            PathBuf::from("packages/app/src/generated/deep/nested.ts"),
+            PathBuf::from("packages/app/src/index.ts"),
+        ]
+    );
+
+    let bounded = options
+        .max_depth(2)
+        .enumerate(monorepo.path(), ["packages/app/tsconfig.json"])
+        .unwrap();
+    assert_eq!(
+        normalize_grouped_separators(bounded)[&scoped_package_name("app")],
+        [PathBuf::from("packages/app/src/index.ts")]
+    );
+}
+
+#[test]
+fn list_estimate_resolves_includes_against_include_base() {
+    let monorepo = generate_monorepo([PackageSpec::new("app", vec![])]);
+    let root = monorepo.path();
+    fs::write(
+        root.join("packages/app/tsconfig.json"),
+        r#"{ "include": ["lib/**/*"] }"#,
+    )
+    .unwrap();
+    fs::create_dir_all(root.join("generated/app/lib")).unwrap();
+    fs::write(root.join("generated/app/lib/view.ts"), "").unwrap();
+
+    let options = EnumerationOptions::default().calculation(Calculation::Estimate);
+    let actual = options
+        .clone()
+        .enumerate(root, ["packages/app/tsconfig.json"])
+        .unwrap();
+    assert!(actual[&scoped_package_name("app")].is_empty());
+
+    let actual = options
+        .include_base("./packages/app/tsconfig.json", "generated/app")
+        .enumerate(root, ["packages/app/tsconfig.json"])
+        .unwrap();
+    assert_eq!(
+        normalize_grouped_separators(actual)[&scoped_package_name("app")],
+        [PathBuf::from("generated/app/lib/view.ts")]
+    );
+}
+
+#[test]
+fn package_includes_file_estimate_honors_include_base() {
+    let monorepo = generate_monorepo([PackageSpec::new("app", vec![])]);
+    let root = monorepo.path();
+    fs::write(
+        root.join("packages/app/tsconfig.json"),
+        r#"{ "include": ["lib/**/*"] }"#,
+    )
+    .unwrap();
+    fs::create_dir_all(root.join("generated/app/lib")).unwrap();
+    fs::write(root.join("generated/app/lib/view.ts"), "").unwrap();
+
+    let options = EnumerationOptions::default()
+        .calculation(Calculation::Estimate)
+        .include_base("packages/app/tsconfig.json", "generated/app");
+    let enumerated = options
+        .enumerate(root, ["packages/app/tsconfig.json"])
+        .unwrap();
+    let includes_file = |file: &str| {
+        options
+            .package_includes_file(root, "packages/app/tsconfig.json", file)
+            .unwrap()
+    };
+    assert_eq!(
+        normalize_grouped_separators(enumerated)[&scoped_package_name("app")],
+        [PathBuf::from("generated/app/lib/view.ts")]
+    );
+    assert!(includes_file("generated/app/lib/view.ts"));
+    assert!(!includes_file("packages/app/src/index.ts"));
+}
+
+#[test]
+fn estimate_reports_missing_internal_dependencies() {
+    let monorepo = generate_monorepo([
+        PackageSpec::new("app", vec!["lib"]),
+        PackageSpec::new("lib", vec![]),
+    ]);
+    fs::write(
+        monorepo.path().join("packages/lib/package.json"),
+        serde_json::json!({
+            "name": scoped_package_name("lib"),
+            "version": "0.0.0",
+            "dependencies": { scoped_package_name("gone"): "workspace:^" },
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let error = tsconfig_includes_by_package_name(monorepo.path(), ["packages/app/tsconfig.json"])
+        .unwrap_err()
+        .to_string();
+    assert_eq!(
+        error,
+        "package @generated/lib depends on @generated/gone, which is not a package in the monorepo"
+    );
+
+    let actual = EnumerationOptions::default()
+        .calculation(Calculation::Estimate)
+        .allow_missing_internal_dependencies(true)
+        .enumerate_detailed(monorepo.path(), ["packages/app/tsconfig.json"])
+        .unwrap();
+    assert_eq!(actual.included_files.len(), 2);
+    assert_eq!(
+        actual.warnings,
+        [Warning::MissingInternalDependency {
+            dependent: scoped_package_name("lib"),
+            missing: scoped_package_name("gone"),
+        }]
+    );
+}
@@ -1,7 +1,26 @@
 use std::collections::HashMap;
+use std::fs;
 use std::path::{Path, PathBuf};
 
-use tsconfig_includes::exact::tsconfig_includes_by_package_name;
+use tsconfig_includes::exact::{
+    self, package_includes_file, tsconfig_include_counts_by_package_name,
+    tsconfig_includes_by_package_name, tsconfig_includes_by_package_name_build,
+    tsconfig_includes_by_package_name_incremental, tsconfig_includes_by_tsconfig, EnumerateError,
+    EnumerateErrorKind,
+};
+use tsconfig_includes::options::{Calculation, EnumerationOptions, ErrorKind};
+use tsconfig_includes::report::render_error_chain;
+use tsconfig_includes::result::Warning;
+
+mod support;
+
+use support::{
+    copy_directory, generate_monorepo, normalize_grouped_separators, scoped_package_name,
+    PackageSpec,
+};
+
+#[cfg(unix)]
+use support::write_stub_compiler;
 
 struct PackageIncludes {
     tsconfig_file: String,
@@ -42,7 +61,10 @@ where
                 })
                 .collect();
 
-            assert_eq!(actual, expected);
+            assert_eq!(
+                normalize_grouped_separators(actual),
+                normalize_grouped_separators(expected)
+            );
         }
         // Don't care what went wrong for now
         Err(err) => {
@@ -76,6 +98,43 @@ fn list_grouped_exact_happy_path_dependencies_bar() {
     );
 }
 
+#[test]
+fn list_grouped_by_tsconfig_exact_happy_path_dependencies_bar() {
+    let actual = tsconfig_includes_by_tsconfig(
+        &PathBuf::from("test-data/happy-path"),
+        ["packages/bar/tsconfig.json"],
+    )
+    .unwrap();
+    let expected: HashMap<PathBuf, Vec<PathBuf>> = [
+        (
+            "packages/bar/tsconfig.json",
+            vec![
+                "packages/bar/src/bin.ts",
+                "packages/bar/src/index.ts",
+                "packages/bar/src/legacy.js",
+            ],
+        ),
+        (
+            "packages/foo/tsconfig.json",
+            vec![
+                "packages/foo/src/data.json",
+                "packages/foo/src/index.ts",
+                "packages/foo/src/lib.ts",
+            ],
+        ),
+    ]
+    .into_iter()
+    .map(|(tsconfig, files)| {
+        (
+            PathBuf::from(tsconfig),
+            files.into_iter().map(PathBuf::from).collect(),
+        )
+    })
+    .collect();
+
+    assert_eq!(actual, expected);
+}
+
 #[test]
 fn list_grouped_exact_happy_path_dependencies_foo() {
     check(
@@ -115,3 +174,690 @@ fn list_grouped_exact_happy_path_dependencies_foo_and_bar() {
         ],
     );
 }
+
+#[test]
+fn list_grouped_exact_build_mode_happy_path() {
+    let actual =
+        tsconfig_includes_by_package_name_build("test-data/happy-path", "tsconfig.json").unwrap();
+    let expected: HashMap<String, Vec<PathBuf>> = [
+        (
+            "@typescript-tools/bar",
+            vec![
+                "packages/bar/src/bin.ts",
+                "packages/bar/src/index.ts",
+                "packages/bar/src/legacy.js",
+            ],
+        ),
+        (
+            "@typescript-tools/foo",
+            vec![
+                "packages/foo/src/data.json",
+                "packages/foo/src/index.ts",
+                "packages/foo/src/lib.ts",
+            ],
+        ),
+    ]
+    .into_iter()
+    .map(|(package, files)| {
+        (
+            package.to_owned(),
+            files.into_iter().map(PathBuf::from).collect(),
+        )
+    })
+    .collect();
+    assert_eq!(
+        normalize_grouped_separators(actual),
+        normalize_grouped_separators(expected)
+    );
+}
+
+fn write_build_info(monorepo_root: &Path, package: &str, file_names: &[&str]) {
+    let dist = monorepo_root.join("packages").join(package).join("dist");
+    fs::create_dir_all(&dist).unwrap();
+    let build_info = serde_json::json!({
+        "program": { "fileNames": file_names },
+        "version": "4.9.5",
+    });
+    fs::write(dist.join("tsconfig.tsbuildinfo"), build_info.to_string()).unwrap();
+}
+
+#[test]
+fn list_grouped_exact_incremental_happy_path_dependencies_bar() {
+    let monorepo = tempfile::tempdir().unwrap();
+    copy_directory(Path::new("test-data/happy-path"), monorepo.path());
+    write_build_info(
+        monorepo.path(),
+        "foo",
+        &[
+            "../../../node_modules/typescript/lib/lib.d.ts",
+            "../src/data.json",
+            "../src/index.ts",
+            "../src/lib.ts",
+        ],
+    );
+    write_build_info(
+        monorepo.path(),
+        "bar",
+        &[
+            "../../../node_modules/typescript/lib/lib.d.ts",
+            "../src/bin.ts",
+            "../src/index.ts",
+            "../src/legacy.js",
+        ],
+    );
+
+    let actual = tsconfig_includes_by_package_name_incremental(
+        monorepo.path(),
+        ["packages/bar/tsconfig.json"],
+    )
+    .unwrap();
+    let expected: HashMap<String, Vec<PathBuf>> = [
+        (
+            "@typescript-tools/bar",
+            vec![
+                "packages/bar/src/bin.ts",
+                "packages/bar/src/index.ts",
+                "packages/bar/src/legacy.js",
+            ],
+        ),
+        (
+            "@typescript-tools/foo",
+            vec![
+                "packages/foo/src/data.json",
+                "packages/foo/src/index.ts",
+                "packages/foo/src/lib.ts",
+            ],
+        ),
+    ]
+    .into_iter()
+    .map(|(package, files)| {
+        (
+            package.to_owned(),
+            files.into_iter().map(PathBuf::from).collect(),
+        )
+    })
+    .collect();
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn verified_happy_path_estimate_agrees_with_exact() {
+    let monorepo_root = PathBuf::from("test-data/happy-path");
+    let tsconfigs = ["packages/foo/tsconfig.json", "packages/bar/tsconfig.json"];
+
+    let actual = EnumerationOptions::default()
+        .calculation(Calculation::Verified)
+        .enumerate(&monorepo_root, tsconfigs)
+        .unwrap();
+    let expected = tsconfig_includes_by_package_name(&monorepo_root, tsconfigs).unwrap();
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn package_includes_file_exact() {
+    let included = |file: &str| {
+        package_includes_file("test-data/happy-path", "packages/bar/tsconfig.json", file).unwrap()
+    };
+
+    assert!(included("packages/bar/src/bin.ts"));
+    assert!(included("packages/bar/src/legacy.js"));
+    assert!(!included("packages/bar/src/missing.ts"));
+}
+
+#[test]
+fn list_grouped_exact_preserves_compile_order() {
+    let monorepo = tempfile::tempdir().unwrap();
+    copy_directory(Path::new("test-data/happy-path"), monorepo.path());
+    write_build_info(
+        monorepo.path(),
+        "foo",
+        &["../src/lib.ts", "../src/index.ts", "../src/data.json"],
+    );
+
+    let actual = EnumerationOptions::default()
+        .use_build_info(true)
+        .preserve_compile_order(true)
+        .enumerate(monorepo.path(), ["packages/foo/tsconfig.json"])
+        .unwrap();
+
+    assert_eq!(
+        actual["@typescript-tools/foo"],
+        [
+            PathBuf::from("packages/foo/src/lib.ts"),
+            PathBuf::from("packages/foo/src/index.ts"),
+            PathBuf::from("packages/foo/src/data.json"),
+        ]
+    );
+}
+
+#[test]
+fn list_grouped_exact_relative_monorepo_root() {
+    let monorepo = tempfile::tempdir_in(".").unwrap();
+    copy_directory(Path::new("test-data/happy-path"), monorepo.path());
+    write_build_info(
+        monorepo.path(),
+        "foo",
+        &["../src/data.json", "../src/index.ts", "../src/lib.ts"],
+    );
+
+    // Relative to the current working directory, with a redundant component
+    let monorepo_root = Path::new("test-data/..").join(monorepo.path().file_name().unwrap());
+    assert!(monorepo_root.is_relative());
+
+    let actual = EnumerationOptions::default()
+        .use_build_info(true)
+        .enumerate(&monorepo_root, ["packages/foo/tsconfig.json"])
+        .unwrap();
+
+    assert_eq!(
+        actual["@typescript-tools/foo"],
+        [
+            PathBuf::from("packages/foo/src/data.json"),
+            PathBuf::from("packages/foo/src/index.ts"),
+            PathBuf::from("packages/foo/src/lib.ts"),
+        ]
+    );
+}
+
+#[test]
+fn count_grouped_exact_happy_path_dependencies_bar() {
+    let actual = tsconfig_include_counts_by_package_name(
+        "test-data/happy-path",
+        ["packages/bar/tsconfig.json"],
+    )
+    .unwrap();
+    let expected = HashMap::from([
+        (String::from("@typescript-tools/bar"), 3),
+        (String::from("@typescript-tools/foo"), 3),
+    ]);
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn list_grouped_exact_skips_declaration_only_packages() {
+    let monorepo = tempfile::tempdir().unwrap();
+    copy_directory(Path::new("test-data/happy-path"), monorepo.path());
+    let src = monorepo.path().join("packages/foo/src");
+    fs::remove_dir_all(&src).unwrap();
+    fs::create_dir_all(&src).unwrap();
+    fs::write(
+        src.join("index.d.ts"),
+        "export declare const foo: string;\n",
+    )
+    .unwrap();
+
+    let actual = EnumerationOptions::default()
+        .skip_declaration_only_packages(true)
+        .enumerate(monorepo.path(), ["packages/foo/tsconfig.json"])
+        .unwrap();
+
+    assert_eq!(
+        actual["@typescript-tools/foo"],
+        [PathBuf::from("packages/foo/src/index.d.ts")]
+    );
+}
+
+#[test]
+fn list_grouped_exact_skips_declaration_only_packages_under_include_base() {
+    let monorepo = generate_monorepo([PackageSpec::new("types", vec![])]);
+    let root = monorepo.path();
+    fs::write(
+        root.join("packages/types/tsconfig.json"),
+        r#"{ "include": ["lib/**/*"] }"#,
+    )
+    .unwrap();
+    fs::create_dir_all(root.join("generated/types/lib")).unwrap();
+    fs::write(
+        root.join("generated/types/lib/index.d.ts"),
+        "export declare const types: string;\n",
+    )
+    .unwrap();
+
+    // The package must be recognized as declaration-only without a compiler
+    let actual = EnumerationOptions::default()
+        .compiler("definitely-not-a-typescript-compiler")
+        .skip_declaration_only_packages(true)
+        .include_base("packages/types/tsconfig.json", "generated/types")
+        .enumerate(root, ["packages/types/tsconfig.json"])
+        .unwrap();
+
+    assert_eq!(
+        actual[&scoped_package_name("types")],
+        [PathBuf::from("generated/types/lib/index.d.ts")]
+    );
+}
+
+#[test]
+fn list_exact_warns_about_empty_project() {
+    let monorepo = tempfile::tempdir().unwrap();
+    copy_directory(Path::new("test-data/happy-path"), monorepo.path());
+    fs::remove_dir_all(monorepo.path().join("packages/foo/src")).unwrap();
+    fs::write(monorepo.path().join("packages/foo/tsconfig.json"), "{}").unwrap();
+
+    let actual = EnumerationOptions::default()
+        .enumerate_detailed(monorepo.path(), ["packages/foo/tsconfig.json"])
+        .unwrap();
+    assert!(actual.included_files["@typescript-tools/foo"].is_empty());
+    assert_eq!(
+        actual.warnings,
+        [Warning::EmptyProject {
+            package: String::from("@typescript-tools/foo")
+        }]
+    );
+}
+
+#[test]
+fn list_exact_best_effort_keeps_files_from_failing_tsc() {
+    let monorepo = tempfile::tempdir().unwrap();
+    copy_directory(Path::new("test-data/happy-path"), monorepo.path());
+    // An unknown compiler option makes tsc exit non-zero after listing files
+    fs::write(
+        monorepo.path().join("packages/foo/tsconfig.json"),
+        r#"{ "compilerOptions": { "notACompilerOption": true }, "include": ["src/**/*"] }"#,
+    )
+    .unwrap();
+
+    let strict =
+        EnumerationOptions::default().enumerate(monorepo.path(), ["packages/foo/tsconfig.json"]);
+    assert!(strict.is_err());
+
+    let actual = EnumerationOptions::default()
+        .best_effort(true)
+        .enumerate_detailed(monorepo.path(), ["packages/foo/tsconfig.json"])
+        .unwrap();
+    assert!(actual.included_files["@typescript-tools/foo"]
+        .contains(&PathBuf::from("packages/foo/src/index.ts")));
+    assert!(matches!(
+        actual.warnings.as_slice(),
+        [Warning::CompilerDiagnostics { package, .. }] if package == "@typescript-tools/foo"
+    ));
+}
+
+#[test]
+fn list_grouped_exact_with_compiler_env() {
+    let actual = EnumerationOptions::default()
+        .compiler_env([("NODE_OPTIONS", "--max-old-space-size=512")])
+        .enumerate("test-data/happy-path", ["packages/foo/tsconfig.json"])
+        .unwrap();
+    let expected =
+        tsconfig_includes_by_package_name("test-data/happy-path", ["packages/foo/tsconfig.json"])
+            .unwrap();
+    assert_eq!(actual, expected);
+}
+
+#[cfg(unix)]
+#[test]
+fn list_grouped_exact_with_alternate_compiler() {
+    let monorepo = generate_monorepo([PackageSpec {
+        files: vec!["App.vue"],
+        ..PackageSpec::new("app", vec![])
+    }]);
+    let bin = tempfile::tempdir().unwrap();
+    let compiler = write_stub_compiler(
+        bin.path(),
+        &["$project/src/App.vue", "$project/src/index.ts"],
+    );
+
+    let actual = EnumerationOptions::default()
+        .compiler(compiler)
+        .enumerate(monorepo.path(), ["packages/app/tsconfig.json"])
+        .unwrap();
+    assert_eq!(
+        actual[&scoped_package_name("app")],
+        [
+            PathBuf::from("packages/app/src/App.vue"),
+            PathBuf::from("packages/app/src/index.ts"),
+        ]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn package_includes_file_and_counts_exact_with_alternate_compiler() {
+    let monorepo = generate_monorepo([PackageSpec {
+        files: vec!["App.vue"],
+        ..PackageSpec::new("app", vec![])
+    }]);
+    let bin = tempfile::tempdir().unwrap();
+    let compiler = write_stub_compiler(
+        bin.path(),
+        &["$project/src/App.vue", "$project/src/index.ts"],
+    );
+    let options = EnumerationOptions::default().compiler(compiler);
+
+    assert!(options
+        .package_includes_file(
+            monorepo.path(),
+            "packages/app/tsconfig.json",
+            "packages/app/src/App.vue"
+        )
+        .unwrap());
+    let actual = options
+        .enumerate_counts(monorepo.path(), ["packages/app/tsconfig.json"])
+        .unwrap();
+    assert_eq!(actual, HashMap::from([(scoped_package_name("app"), 2)]));
+}
+
+#[cfg(unix)]
+#[test]
+fn list_exact_drops_listed_files_missing_from_disk() {
+    let monorepo = generate_monorepo([PackageSpec::new("app", vec![])]);
+    let bin = tempfile::tempdir().unwrap();
+    let compiler = write_stub_compiler(
+        bin.path(),
+        &["$project/src/index.ts", "$project/src/deleted.ts"],
+    );
+    let options = EnumerationOptions::default().compiler(compiler);
+
+    let unchecked = options
+        .clone()
+        .enumerate(monorepo.path(), ["packages/app/tsconfig.json"])
+        .unwrap();
+    assert_eq!(unchecked[&scoped_package_name("app")].len(), 2);
+
+    let actual = options
+        .check_files_exist(true)
+        .enumerate_detailed(monorepo.path(), ["packages/app/tsconfig.json"])
+        .unwrap();
+    assert_eq!(
+        actual.included_files[&scoped_package_name("app")],
+        [PathBuf::from("packages/app/src/index.ts")]
+    );
+    assert_eq!(
+        actual.warnings,
+        [Warning::MissingEnumeratedFile {
+            package: scoped_package_name("app"),
+            file: PathBuf::from("packages/app/src/deleted.ts"),
+        }]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn list_exact_falls_back_to_list_files_for_legacy_compilers() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let monorepo = generate_monorepo([PackageSpec::new("app", vec![])]);
+    let bin = tempfile::tempdir().unwrap();
+    // Mimics a compiler predating --listFilesOnly, which prints its
+    // diagnostics to stdout
+    let compiler = bin.path().join("tsc");
+    fs::write(
+        &compiler,
+        "#!/bin/sh\n\
+         if [ \"$1\" = --listFilesOnly ]; then\n\
+         echo \"error TS5023: Unknown compiler option '--listFilesOnly'.\"\n\
+         exit 1\n\
+         fi\n\
+         project=$(cd \"$(dirname \"$6\")\" && pwd -P)\n\
+         echo /opt/node_modules/typescript/lib/lib.d.ts\n\
+         echo \"$project/src/index.ts\"\n\
+         echo \"$project/src/index.ts(1,1): message TS6194: Found 0 errors.\"\n",
+    )
+    .unwrap();
+    fs::set_permissions(&compiler, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let actual = EnumerationOptions::default()
+        .compiler(compiler)
+        .enumerate(monorepo.path(), ["packages/app/tsconfig.json"])
+        .unwrap();
+    assert_eq!(
+        actual[&scoped_package_name("app")],
+        [PathBuf::from("packages/app/src/index.ts")]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn list_exact_reports_legacy_compiler_type_errors_as_diagnostics() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let monorepo = generate_monorepo([PackageSpec::new("app", vec![])]);
+    let bin = tempfile::tempdir().unwrap();
+    // Mimics a compiler predating --listFilesOnly finding a type error, which
+    // makes it exit non-zero after listing every file
+    let compiler = bin.path().join("tsc");
+    fs::write(
+        &compiler,
+        "#!/bin/sh\n\
+         if [ \"$1\" = --listFilesOnly ]; then\n\
+         echo \"error TS5023: Unknown compiler option '--listFilesOnly'.\"\n\
+         exit 1\n\
+         fi\n\
+         project=$(cd \"$(dirname \"$6\")\" && pwd -P)\n\
+         echo /opt/node_modules/typescript/lib/lib.d.ts\n\
+         echo \"$project/src/index.ts\"\n\
+         echo \"src/index.ts(1,7): error TS2322: Type 'string' is not assignable to type 'number'.\" >&2\n\
+         exit 2\n",
+    )
+    .unwrap();
+    fs::set_permissions(&compiler, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let actual = EnumerationOptions::default()
+        .compiler(compiler)
+        .enumerate_detailed(monorepo.path(), ["packages/app/tsconfig.json"])
+        .unwrap();
+    assert_eq!(
+        actual.included_files[&scoped_package_name("app")],
+        [PathBuf::from("packages/app/src/index.ts")]
+    );
+    assert!(matches!(
+        actual.warnings.as_slice(),
+        [Warning::CompilerDiagnostics { package, stderr }]
+            if *package == scoped_package_name("app") && stderr.contains("TS2322")
+    ));
+}
+
+#[test]
+fn list_exact_reports_missing_compiler() {
+    let err = EnumerationOptions::default()
+        .compiler("definitely-not-a-typescript-compiler")
+        .enumerate("test-data/happy-path", ["packages/foo/tsconfig.json"])
+        .unwrap_err();
+    let rendered = render_error_chain(&err);
+    assert!(
+        rendered.contains("definitely-not-a-typescript-compiler"),
+        "{}",
+        rendered
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn list_exact_missing_compiler_kind_names_program() {
+    let err = EnumerationOptions::default()
+        .compiler("definitely-not-a-typescript-compiler")
+        .enumerate("test-data/happy-path", ["packages/foo/tsconfig.json"])
+        .unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::Exact { .. }));
+    // Wrapped errors carry their own kind, so look for the compiler's
+    let chain: Vec<&dyn std::error::Error> =
+        std::iter::successors(Some(&err as &dyn std::error::Error), |err| err.source()).collect();
+    let exact_err = chain
+        .iter()
+        .find_map(|err| err.downcast_ref::<exact::Error>())
+        .expect("error chain should include the exact enumeration error");
+    assert!(matches!(
+        exact_err.kind(),
+        exact::ErrorKind::Enumerate { .. }
+    ));
+    let err = chain
+        .iter()
+        .find_map(|err| err.downcast_ref::<EnumerateError>())
+        .expect("error chain should include the compiler error");
+    match err.kind() {
+        EnumerateErrorKind::CompilerNotFound { program, .. } => {
+            assert_eq!(program, "definitely-not-a-typescript-compiler")
+        }
+        kind => panic!("expected a missing compiler, got {:?}", kind),
+    }
+}
+
+#[test]
+fn list_grouped_exact_strips_ansi_escapes() {
+    let monorepo = generate_monorepo([PackageSpec {
+        files: vec!["dist[0m.ts"],
+        ..PackageSpec::new("app", vec![])
+    }]);
+    let bin = tempfile::tempdir().unwrap();
+    let compiler = write_stub_compiler(
+        bin.path(),
+        &[
+            "\\033[32m$project/src/index.ts\\033[0m",
+            "$project/src/dist[0m.ts",
+        ],
+    );
+
+    let actual = EnumerationOptions::default()
+        .compiler(compiler)
+        .enumerate(monorepo.path(), ["packages/app/tsconfig.json"])
+        .unwrap();
+    assert_eq!(
+        actual[&scoped_package_name("app")],
+        [
+            PathBuf::from("packages/app/src/dist[0m.ts"),
+            PathBuf::from("packages/app/src/index.ts"),
+        ]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn list_grouped_by_tsconfig_exact_test_tsconfig_for_target_package() {
+    let monorepo = generate_monorepo([PackageSpec::new("a", vec![])]);
+    fs::write(
+        monorepo.path().join("packages/a/tsconfig.test.json"),
+        r#"{ "extends": "./tsconfig.json", "include": ["test/**/*"] }"#,
+    )
+    .unwrap();
+    let bin = tempfile::tempdir().unwrap();
+    // Answer with the name of the tsconfig tsc was pointed at
+    let compiler = write_stub_compiler(bin.path(), &["$project/$(basename \"$5\")"]);
+
+    let actual = EnumerationOptions::default()
+        .compiler(compiler)
+        .enumerate_by_tsconfig(monorepo.path(), ["packages/a/tsconfig.test.json"])
+        .unwrap();
+    assert_eq!(
+        actual[Path::new("packages/a/tsconfig.test.json")],
+        [PathBuf::from("packages/a/tsconfig.test.json")]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn list_grouped_exact_transforms_paths() {
+    let monorepo = generate_monorepo([PackageSpec::new("a", vec![])]);
+    let bin = tempfile::tempdir().unwrap();
+    let compiler = write_stub_compiler(
+        bin.path(),
+        &[
+            "$project/src/index.ts",
+            "$project/src/__generated__/schema.ts",
+            "$project/src/index.ts",
+        ],
+    );
+
+    let actual = EnumerationOptions::default()
+        .compiler(compiler)
+        .preserve_compile_order(true)
+        .transform_paths(|file| {
+            (!file.to_string_lossy().contains("__generated__")).then(|| file.to_owned())
+        })
+        .enumerate(monorepo.path(), ["packages/a/tsconfig.json"])
+        .unwrap();
+    assert_eq!(
+        actual[&scoped_package_name("a")],
+        [PathBuf::from("packages/a/src/index.ts")]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn list_grouped_exact_build_mode_attributes_files_by_directory() {
+    let monorepo = generate_monorepo([
+        PackageSpec::new("a", vec!["b"]),
+        PackageSpec::new("b", vec![]),
+        PackageSpec::new("a/nested", vec![]),
+    ]);
+    fs::write(
+        monorepo.path().join("tsconfig.json"),
+        r#"{ "files": [], "references": [{ "path": "packages/a" }, { "path": "packages/b" }] }"#,
+    )
+    .unwrap();
+    let root = fs::canonicalize(monorepo.path()).unwrap();
+    let nested = format!("{}/packages/a/nested/src/index.ts", root.display());
+    let shared = format!("{}/shared/util.ts", root.display());
+    let bin = tempfile::tempdir().unwrap();
+    let compiler = write_stub_compiler(bin.path(), &["$project/src/index.ts", &nested, &shared]);
+
+    let actual = EnumerationOptions::default()
+        .compiler(compiler)
+        .enumerate_build(monorepo.path(), "tsconfig.json")
+        .unwrap();
+    let expected: HashMap<String, Vec<PathBuf>> = [
+        ("a", "packages/a/src/index.ts"),
+        ("a/nested", "packages/a/nested/src/index.ts"),
+        ("b", "packages/b/src/index.ts"),
+    ]
+    .into_iter()
+    .map(|(package, file)| (scoped_package_name(package), vec![PathBuf::from(file)]))
+    .collect();
+    assert_eq!(actual, expected);
+}
+
+#[cfg(unix)]
+#[test]
+fn list_exact_build_mode_warns_about_missing_files() {
+    let monorepo =
+        generate_monorepo([PackageSpec::new("a", vec![]), PackageSpec::new("b", vec![])]);
+    fs::write(
+        monorepo.path().join("tsconfig.json"),
+        r#"{ "files": [], "references": [{ "path": "packages/a" }, { "path": "packages/b" }] }"#,
+    )
+    .unwrap();
+    let bin = tempfile::tempdir().unwrap();
+    let compiler = write_stub_compiler(
+        bin.path(),
+        &["$project/src/index.ts", "$project/src/deleted.ts"],
+    );
+
+    let actual = EnumerationOptions::default()
+        .compiler(compiler)
+        .check_files_exist(true)
+        .enumerate_build_detailed(monorepo.path(), "tsconfig.json")
+        .unwrap();
+    assert_eq!(
+        actual.included_files[&scoped_package_name("a")],
+        [PathBuf::from("packages/a/src/index.ts")]
+    );
+    assert_eq!(
+        actual.warnings,
+        ["a", "b"].map(|package| Warning::MissingEnumeratedFile {
+            package: scoped_package_name(package),
+            file: PathBuf::from(format!("packages/{}/src/deleted.ts", package)),
+        })
+    );
+}
+
+#[test]
+fn exact_reports_duplicate_package_names() {
+    let monorepo =
+        generate_monorepo([PackageSpec::new("a", vec![]), PackageSpec::new("b", vec![])]);
+    fs::write(
+        monorepo.path().join("packages/b/package.json"),
+        serde_json::json!({ "name": scoped_package_name("a"), "version": "0.0.0" }).to_string(),
+    )
+    .unwrap();
+
+    let error = tsconfig_includes_by_package_name(monorepo.path(), ["packages/a/tsconfig.json"])
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.contains("both declare the name @generated/a"),
+        "{}",
+        error
+    );
+}
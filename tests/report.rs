@@ -0,0 +1,68 @@
+use std::fmt::Display;
+
+use tsconfig_includes::report::{render_error_chain, ErrorChain};
+
+#[derive(Debug)]
+struct Layer {
+    message: &'static str,
+    source: Option<Box<Layer>>,
+}
+
+impl Layer {
+    fn chain(messages: &[&'static str]) -> Self {
+        let (message, causes) = messages.split_first().unwrap();
+        Self {
+            message,
+            source: (!causes.is_empty()).then(|| Box::new(Layer::chain(causes))),
+        }
+    }
+}
+
+impl Display for Layer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for Layer {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|source| source as &(dyn std::error::Error + 'static))
+    }
+}
+
+#[test]
+fn render_error_without_source() {
+    let err = Layer::chain(&["unable to estimate tsconfig includes"]);
+    assert_eq!(
+        render_error_chain(&err),
+        "unable to estimate tsconfig includes"
+    );
+}
+
+#[test]
+fn render_nested_error_chain() {
+    let err = Layer::chain(&[
+        "unable to estimate tsconfig includes",
+        "unable to read file",
+        "No such file or directory",
+    ]);
+    assert_eq!(
+        render_error_chain(&err),
+        "unable to estimate tsconfig includes\n  caused by: unable to read file\n    caused by: No such file or directory"
+    );
+    assert_eq!(ErrorChain(&err).to_string(), render_error_chain(&err));
+}
+
+#[test]
+fn render_crate_error_chain() {
+    let err = tsconfig_includes::estimate::tsconfig_includes_by_package_name(
+        "test-data/does-not-exist",
+        ["packages/foo/tsconfig.json"],
+    )
+    .unwrap_err();
+    let rendered = render_error_chain(&err);
+    assert!(rendered.starts_with(&err.to_string()));
+    assert!(rendered.contains("\n  caused by: "));
+}
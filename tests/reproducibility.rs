@@ -0,0 +1,96 @@
+//! Enumeration depends only on its inputs on disk, not on the working
+//! directory or environment of the calling process.
+//!
+//! Both are process-wide, so these checks share a single test in their own
+//! test binary rather than racing the other integration tests.
+
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
+
+use tsconfig_includes::options::{Calculation, EnumerationOptions};
+
+mod support;
+
+use support::{generate_monorepo, scoped_package_name, PackageSpec};
+
+#[cfg(unix)]
+use support::write_stub_compiler;
+
+fn enumerate_from(
+    working_directory: &Path,
+    monorepo_root: &Path,
+    options: &EnumerationOptions,
+) -> HashMap<String, Vec<PathBuf>> {
+    env::set_current_dir(working_directory).unwrap();
+    options
+        .clone()
+        .enumerate(
+            monorepo_root,
+            ["packages/app/tsconfig.json", "packages/lib/tsconfig.json"],
+        )
+        .unwrap()
+}
+
+#[test]
+fn enumeration_is_independent_of_working_directory_and_environment() {
+    let original_directory = env::current_dir().unwrap();
+    let monorepo = generate_monorepo([
+        PackageSpec::new("lib", vec![]),
+        PackageSpec::new("app", vec!["lib"]),
+    ]);
+    let elsewhere = tempfile::tempdir().unwrap();
+
+    let options = EnumerationOptions::default().calculation(Calculation::Estimate);
+    let from_root = enumerate_from(monorepo.path(), monorepo.path(), &options);
+    assert_eq!(
+        from_root,
+        enumerate_from(elsewhere.path(), monorepo.path(), &options)
+    );
+    assert_eq!(
+        from_root,
+        enumerate_from(monorepo.path(), Path::new("."), &options)
+    );
+
+    #[cfg(unix)]
+    {
+        // tsc must not see this unless the environment is inherited
+        env::set_var("TSCONFIG_INCLUDES_AMBIENT", "1");
+        let bin = monorepo.path().join("bin");
+        std::fs::create_dir(&bin).unwrap();
+        write_stub_compiler(
+            &bin,
+            &[
+                "$project/src/index.ts",
+                "${TSCONFIG_INCLUDES_AMBIENT:+$project/src/ambient.ts}",
+            ],
+        );
+        // Relative compiler paths are resolved against the monorepo root
+        let options = EnumerationOptions::default().compiler("bin/vue-tsc");
+
+        let from_root = enumerate_from(monorepo.path(), monorepo.path(), &options);
+        assert_eq!(
+            from_root,
+            enumerate_from(elsewhere.path(), monorepo.path(), &options)
+        );
+        assert_eq!(
+            from_root[&scoped_package_name("app")],
+            [PathBuf::from("packages/app/src/index.ts")]
+        );
+
+        let inherited = enumerate_from(
+            elsewhere.path(),
+            monorepo.path(),
+            &options.inherit_compiler_env(true),
+        );
+        assert_eq!(
+            inherited[&scoped_package_name("app")],
+            [
+                PathBuf::from("packages/app/src/ambient.ts"),
+                PathBuf::from("packages/app/src/index.ts"),
+            ]
+        );
+    }
+
+    env::set_current_dir(original_directory).unwrap();
+}
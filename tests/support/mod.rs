@@ -0,0 +1,183 @@
+//! Helpers shared by the integration tests.
+
+// Each integration test compiles this module separately and uses a subset
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::fs;
+use std::hash::Hash;
+use std::path::{Path, PathBuf};
+
+use tempfile::TempDir;
+
+/// A package in a generated monorepo.
+pub struct PackageSpec {
+    /// Unscoped package name, also used as the package's directory name.
+    pub name: &'static str,
+    /// Unscoped names of the internal packages this package depends on.
+    pub dependencies: Vec<&'static str>,
+    /// Source files beneath the package's `src` directory, in addition to
+    /// the `index.ts` importing every dependency.
+    pub files: Vec<&'static str>,
+}
+
+impl PackageSpec {
+    pub fn new(name: &'static str, dependencies: Vec<&'static str>) -> Self {
+        Self {
+            name,
+            dependencies,
+            files: Vec::new(),
+        }
+    }
+}
+
+/// Scope of every generated package name.
+pub const SCOPE: &str = "@generated";
+
+/// Scoped name of the generated package named `name`.
+pub fn scoped_package_name(name: &str) -> String {
+    format!("{}/{}", SCOPE, name)
+}
+
+/// Write a lerna monorepo into a temporary directory, with one package per
+/// spec under `packages/`. Each package has a `package.json` declaring its
+/// internal dependencies, a `tsconfig.json` including `src/**/*`, and a
+/// `src/index.ts` importing each dependency.
+pub fn generate_monorepo<I>(spec: I) -> TempDir
+where
+    I: IntoIterator<Item = PackageSpec>,
+{
+    let monorepo = tempfile::tempdir().unwrap();
+    let packages: Vec<PackageSpec> = spec.into_iter().collect();
+
+    let lerna_manifest = serde_json::json!({
+        "packages": packages
+            .iter()
+            .map(|package| format!("packages/{}", package.name))
+            .collect::<Vec<_>>(),
+    });
+    fs::write(
+        monorepo.path().join("lerna.json"),
+        lerna_manifest.to_string(),
+    )
+    .unwrap();
+
+    for package in &packages {
+        let package_directory = monorepo.path().join("packages").join(package.name);
+        let src = package_directory.join("src");
+        fs::create_dir_all(&src).unwrap();
+
+        let dependencies: serde_json::Map<String, serde_json::Value> = package
+            .dependencies
+            .iter()
+            .map(|dependency| (scoped_package_name(dependency), "0.0.0".into()))
+            .collect();
+        let package_manifest = serde_json::json!({
+            "name": scoped_package_name(package.name),
+            "version": "0.0.0",
+            "private": true,
+            "dependencies": dependencies,
+        });
+        fs::write(
+            package_directory.join("package.json"),
+            package_manifest.to_string(),
+        )
+        .unwrap();
+
+        let references: Vec<_> = package
+            .dependencies
+            .iter()
+            .map(|dependency| serde_json::json!({ "path": format!("../{}", dependency) }))
+            .collect();
+        let tsconfig = serde_json::json!({
+            "include": ["src/**/*"],
+            "compilerOptions": { "outDir": "dist", "composite": true },
+            "references": references,
+        });
+        fs::write(
+            package_directory.join("tsconfig.json"),
+            tsconfig.to_string(),
+        )
+        .unwrap();
+
+        let index: String = package
+            .dependencies
+            .iter()
+            .map(|dependency| format!("export * from '{}';\n", scoped_package_name(dependency)))
+            .collect();
+        fs::write(src.join("index.ts"), index).unwrap();
+        for file in &package.files {
+            let file = src.join(file);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, "export {};\n").unwrap();
+        }
+    }
+
+    monorepo
+}
+
+/// Recursively copy the directory `source` to `destination`.
+pub fn copy_directory(source: &Path, destination: &Path) {
+    fs::create_dir_all(destination).unwrap();
+    for entry in fs::read_dir(source).unwrap() {
+        let entry = entry.unwrap();
+        let destination = destination.join(entry.file_name());
+        if entry.file_type().unwrap().is_dir() {
+            copy_directory(&entry.path(), &destination);
+        } else {
+            fs::copy(entry.path(), destination).unwrap();
+        }
+    }
+}
+
+/// Write an executable stub compiler answering `--listFilesOnly --pretty
+/// false --project <tsconfig>` by printing each of `lines`, along with a TypeScript library file outside the
+/// monorepo. Lines may refer to the tsconfig's directory as `$project` and
+/// contain `printf` escapes like `\033`.
+#[cfg(unix)]
+pub fn write_stub_compiler(directory: &Path, lines: &[&str]) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let printed_lines: String = lines
+        .iter()
+        .map(|line| format!("printf '%b\\n' \"{}\"\n", line))
+        .collect();
+    let script = format!(
+        "#!/bin/sh\n\
+         project=$(cd \"$(dirname \"$5\")\" && pwd -P)\n\
+         {}echo /opt/node_modules/typescript/lib/lib.d.ts\n",
+        printed_lines
+    );
+    let compiler = directory.join("vue-tsc");
+    fs::write(&compiler, script).unwrap();
+    fs::set_permissions(&compiler, fs::Permissions::from_mode(0o755)).unwrap();
+    compiler
+}
+
+/// Express `path` with forward slashes, so expectations written with `/`
+/// hold on platforms using another separator.
+pub fn normalize_separators(path: &Path) -> PathBuf {
+    PathBuf::from(
+        path.to_string_lossy()
+            .replace(std::path::MAIN_SEPARATOR, "/"),
+    )
+}
+
+/// Apply [`normalize_separators`] to every path in an enumeration result.
+pub fn normalize_grouped_separators<K>(
+    grouped: HashMap<K, Vec<PathBuf>>,
+) -> HashMap<K, Vec<PathBuf>>
+where
+    K: Eq + Hash,
+{
+    grouped
+        .into_iter()
+        .map(|(key, paths)| {
+            let paths = paths
+                .iter()
+                .map(|path| normalize_separators(path))
+                .collect();
+            (key, paths)
+        })
+        .collect()
+}